@@ -1,11 +1,22 @@
+use std::{collections::HashMap, time::Instant};
+
+use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum Column {
-    Todo,
-    Wip,
-    Done,
-}
+use crate::{
+    archive::Rollover,
+    history::History,
+    journal::{Event, EventKind},
+    messages::Messages,
+    pomodoro::{Phase, Pomodoro, PomodoroConfig},
+    report::Report,
+    search::fuzzy_match,
+    task::{
+        deserialize_optional_task, deserialize_tasks, parse_input, Step, Task, LOWEST_PRIORITY,
+    },
+};
+
+const NO_WIP_COLUMN: &str = "The board has no wip column";
 
 #[derive(Debug, Clone)]
 pub enum Mode {
@@ -13,7 +24,20 @@ pub enum Mode {
     Help,
     Focus,
     Overview,
-    Edit(usize),
+    // the views that work on a single task hold on to its id, which a reload
+    // of the board can't point at another task
+    Edit(u64),
+    Boards,
+    AddBoard,
+    Search,
+    Filter,
+    Describe(u64),
+    Checklist(u64),
+    AddStep(u64),
+    Blockers(u64),
+    Archive,
+    Log,
+    Stats,
 }
 
 #[derive(Deserialize, PartialEq, Debug, Clone)]
@@ -22,24 +46,17 @@ pub struct ListColumn<T> {
     pub items: Vec<T>,
 }
 
-impl ListColumn<String> {
-    fn new() -> ListColumn<String> {
-        ListColumn {
-            index: None,
-            items: vec![],
-        }
-    }
-
-    pub fn push(&mut self, item: String) {
+impl<T: Clone> ListColumn<T> {
+    pub fn push(&mut self, item: T) {
         self.items.push(item);
         self.index = Some(self.items.len() - 1);
     }
 
-    pub fn remove(&mut self) -> Option<String> {
+    pub fn remove(&mut self) -> Option<T> {
         if let Some(item) = self.current_item() {
             let index = self.index.unwrap();
             self.items.remove(index);
-            if self.items.len() == 0 {
+            if self.items.is_empty() {
                 self.index = None;
             } else {
                 if index + 1 > self.items.len() {
@@ -54,7 +71,7 @@ impl ListColumn<String> {
         }
     }
 
-    pub fn current_item(&mut self) -> Option<String> {
+    pub fn current_item(&mut self) -> Option<T> {
         if let Some(index) = self.index {
             self.items.get(index).cloned()
         } else {
@@ -62,20 +79,22 @@ impl ListColumn<String> {
         }
     }
 
-    pub fn move_item_up(&mut self) {
-        if let Some(index) = self.index {
-            if index > 0 {
-                self.index = Some(index - 1);
-                self.items.swap(index, index - 1);
-            }
-        }
+    // puts an item at `index` without moving the cursor off the item it was on
+    pub fn insert(&mut self, index: usize, item: T) {
+        self.items.insert(index, item);
+        self.index = match self.index {
+            Some(i) if i >= index => Some(i + 1),
+            Some(i) => Some(i),
+            None => Some(index),
+        };
     }
 
-    pub fn move_item_down(&mut self) {
+    // swaps the selected item with the one at `to` and follows it there
+    pub fn move_item_to(&mut self, to: usize) {
         if let Some(index) = self.index {
-            if index < self.items.len() - 1 {
-                self.index = Some(index + 1);
-                self.items.swap(index, index + 1);
+            if to < self.items.len() {
+                self.items.swap(index, to);
+                self.index = Some(to);
             }
         }
     }
@@ -85,15 +104,71 @@ impl ListColumn<String> {
             .index
             .filter(|&i| i + 1 < self.items.len())
             .map(|i| i + 1)
-            .or_else(|| Some(self.items.len() - 1));
+            .or_else(|| self.items.len().checked_sub(1));
     }
 
     pub fn up(&mut self) {
         self.index = self.index.filter(|&i| i > 0).map(|i| i - 1);
     }
 
-    pub fn replace_at(&mut self, index: usize, value: String) {
-        self.items[index] = value;
+    pub fn selected(&self) -> Option<&T> {
+        self.index.and_then(|index| self.items.get(index))
+    }
+
+    pub fn selected_mut(&mut self) -> Option<&mut T> {
+        self.index.and_then(|index| self.items.get_mut(index))
+    }
+
+    // swaps in a new set of items, keeping the cursor as close as possible to where it was
+    fn replace_items(&mut self, items: Vec<T>) {
+        self.items = items;
+        self.index = match self.items.len() {
+            0 => None,
+            len => Some(self.index.unwrap_or(0).min(len - 1)),
+        };
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub name: String,
+    // the column where work happens, one task at a time unless it has a limit of its own
+    pub wip: bool,
+    pub limit: Option<usize>,
+    pub tasks: ListColumn<Task>,
+}
+
+impl Column {
+    /// How many tasks the column may hold, if there is a limit at all.
+    pub fn limit(&self) -> Option<usize> {
+        self.limit.or(if self.wip { Some(1) } else { None })
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.limit()
+            .is_some_and(|limit| self.tasks.items.len() >= limit)
+    }
+}
+
+impl From<BoardColumn> for Column {
+    fn from(column: BoardColumn) -> Column {
+        Column {
+            name: column.name,
+            wip: column.wip,
+            limit: column.limit,
+            tasks: ListColumn::from(column.items),
+        }
+    }
+}
+
+impl From<&Column> for BoardColumn {
+    fn from(column: &Column) -> BoardColumn {
+        BoardColumn {
+            name: column.name.clone(),
+            wip: column.wip,
+            limit: column.limit,
+            items: column.tasks.items.clone(),
+        }
     }
 }
 
@@ -101,45 +176,277 @@ impl ListColumn<String> {
 pub struct App {
     pub input: String,
     pub current_mode: Mode,
-    pub current_column: Column,
-    pub todo: ListColumn<String>,
-    pub wip: Option<String>,
-    pub done: ListColumn<String>,
+    // columns run from the first one, where tasks get added, to the last one,
+    // where finished tasks end up
+    pub columns: Vec<Column>,
+    pub current_column: usize,
+    pub next_id: u64,
+    pub pomodoro: Pomodoro,
+    // set when a pomodoro phase ends, the terminal rings and clears it
+    pub bell: bool,
+    pub history: History,
+    // whether the undo history is saved along with the board
+    pub keep_history: bool,
+    // moving right with nothing selected takes the most urgent task into wip
+    pub pull_top_priority: bool,
+    // shown at the bottom of the screen until dealt with, e.g. a conflict on disk
+    pub status: Option<String>,
+    // why the last actions did nothing, shown at the bottom of the screen for a moment
+    pub messages: Messages,
+    // bumped on every change to the board, tells the ui loop when to save
+    pub revision: u64,
+    // the file changed on disk while there were unsaved changes
+    pub conflict: bool,
+    // set when another ow holds the board, explains who
+    pub read_only: Option<String>,
+    // every board in the file, the current one is only brought up to date
+    // when switching away from it or saving
+    pub boards: Vec<Board>,
+    pub current_board: usize,
+    pub one_wip: bool,
+    // the board highlighted in the board picker
+    pub picked_board: usize,
+    // the last search, n and N jump between the tasks it matches
+    pub search: Option<String>,
+    // only tasks with this tag are shown and can be selected
+    pub filter: Option<String>,
+    // whether the overview shows everything about the selected task under the columns
+    pub show_details: bool,
+    // the step highlighted in the checklist view
+    pub step_index: usize,
+    // finished tasks taken off the board, the most recent first
+    pub archive: ListColumn<Task>,
+    // finished tasks move to the archive on their own after a while
+    pub archive_after: Option<Rollover>,
+    // what happened to the tasks since the journal was last written
+    pub journal: Vec<Event>,
+    // the journal entries of the task the log view was opened on
+    pub log: ListColumn<Event>,
+    // the journal as it was when the stats screen was opened, and how many days it covers
+    pub stats_journal: Vec<Event>,
+    pub stats_days: i64,
+}
+
+/// Everything in a board file. A file with a single board is written the way
+/// it was before there could be several of them.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Boards {
+    pub boards: Vec<Board>,
+    // a single task in progress across all the boards rather than one per board
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub one_wip: bool,
+}
+
+impl Boards {
+    pub fn single(&self) -> Option<&Board> {
+        match self.boards.as_slice() {
+            [board] if !self.one_wip => Some(board),
+            _ => None,
+        }
+    }
+}
+
+impl From<Board> for Boards {
+    fn from(board: Board) -> Boards {
+        Boards {
+            boards: vec![board],
+            one_wip: false,
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct Board {
-    todo: Vec<String>,
-    wip: Option<String>,
-    done: Vec<String>,
+    // boards are only named once a file has more than one
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    name: String,
+    #[serde(default)]
+    next_id: u64,
+    #[serde(default)]
+    columns: Vec<BoardColumn>,
+    // boards saved before columns could be configured only had these three
+    #[serde(default, skip_serializing, deserialize_with = "deserialize_tasks")]
+    todo: Vec<Task>,
+    #[serde(
+        default,
+        skip_serializing,
+        deserialize_with = "deserialize_optional_task"
+    )]
+    wip: Option<Task>,
+    #[serde(default, skip_serializing, deserialize_with = "deserialize_tasks")]
+    done: Vec<Task>,
+    #[serde(default)]
+    pomodoro: PomodoroConfig,
+    #[serde(default, skip_serializing_if = "is_false")]
+    keep_history: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pull_top_priority: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    archive_after: Option<Rollover>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    archive: Vec<Task>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    history: Option<History>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct BoardColumn {
+    name: String,
+    #[serde(default, skip_serializing_if = "is_false")]
+    wip: bool,
+    // the wip column defaults to a single task, other columns to no limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    limit: Option<usize>,
+    #[serde(default, deserialize_with = "deserialize_tasks")]
+    items: Vec<Task>,
+}
+
+impl BoardColumn {
+    fn new(name: &str, wip: bool, items: Vec<Task>) -> BoardColumn {
+        BoardColumn {
+            name: name.to_string(),
+            wip,
+            limit: None,
+            items,
+        }
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl Board {
+    fn empty(name: &str) -> Board {
+        Board {
+            name: name.to_string(),
+            next_id: 1,
+            columns: vec![],
+            todo: vec![],
+            wip: None,
+            done: vec![],
+            pomodoro: PomodoroConfig::default(),
+            keep_history: false,
+            pull_top_priority: false,
+            archive_after: None,
+            archive: vec![],
+            history: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self.name.as_str() {
+            "" => "default",
+            name => name,
+        }
+    }
+
+    pub fn wip_task(&self) -> Option<&Task> {
+        self.columns
+            .iter()
+            .find(|column| column.wip)
+            .and_then(|column| column.items.first())
+            .or(self.wip.as_ref())
+    }
+
+    fn take_columns(&mut self) -> Vec<BoardColumn> {
+        if !self.columns.is_empty() {
+            return std::mem::take(&mut self.columns);
+        }
+        vec![
+            BoardColumn::new("Todo", false, std::mem::take(&mut self.todo)),
+            BoardColumn::new("Wip", true, self.wip.take().into_iter().collect()),
+            BoardColumn::new("Done", false, std::mem::take(&mut self.done)),
+        ]
+    }
 }
 
 impl From<Board> for App {
-    fn from(board: Board) -> App {
-        App {
+    fn from(mut board: Board) -> App {
+        let columns = board.take_columns();
+        let mut app = App {
+            boards: vec![Board::empty(&board.name)],
+            current_board: 0,
+            one_wip: false,
+            picked_board: 0,
+            search: None,
+            filter: None,
+            show_details: false,
+            step_index: 0,
+            archive: ListColumn::from(board.archive),
+            archive_after: board.archive_after,
+            journal: vec![],
+            log: ListColumn::from(vec![]),
+            stats_journal: vec![],
+            stats_days: 7,
             input: String::new(),
             current_mode: Mode::Overview,
-            current_column: Column::Todo,
-            todo: ListColumn::from(board.todo),
-            wip: board.wip,
-            done: ListColumn::from(board.done),
+            columns: columns.into_iter().map(Column::from).collect(),
+            current_column: 0,
+            next_id: board.next_id.max(1),
+            pomodoro: Pomodoro::new(board.pomodoro),
+            bell: false,
+            history: board.history.unwrap_or_default(),
+            keep_history: board.keep_history,
+            pull_top_priority: board.pull_top_priority,
+            status: None,
+            messages: Messages::default(),
+            revision: 0,
+            conflict: false,
+            read_only: None,
+        };
+        app.assign_missing_ids();
+        app.start_missing_clocks(Local::now());
+        app
+    }
+}
+
+impl From<&App> for Board {
+    fn from(app: &App) -> Board {
+        let mut board = app.snapshot();
+        if app.keep_history {
+            board.history = Some(app.history.clone());
         }
+        board
     }
 }
 
-impl From<App> for Board {
-    fn from(board: App) -> Board {
-        Board {
-            todo: board.todo.items,
-            wip: board.wip,
-            done: board.done.items,
+impl From<Boards> for App {
+    fn from(mut boards: Boards) -> App {
+        if boards.boards.is_empty() {
+            boards.boards.push(Board::empty(""));
+        }
+        let mut app = App::from(boards.boards[0].clone());
+        app.boards = boards.boards;
+        app.one_wip = boards.one_wip;
+        app
+    }
+}
+
+impl From<&App> for Boards {
+    fn from(app: &App) -> Boards {
+        let mut boards = app.boards.clone();
+        boards[app.current_board] = Board::from(app);
+        // other boards keep their undo history around for as long as ow runs
+        for board in boards.iter_mut().filter(|board| !board.keep_history) {
+            board.history = None;
         }
+        Boards {
+            boards,
+            one_wip: app.one_wip,
+        }
+    }
+}
+
+impl From<App> for Boards {
+    fn from(app: App) -> Boards {
+        Boards::from(&app)
     }
 }
 
 impl<T> From<Vec<T>> for ListColumn<T> {
     fn from(items: Vec<T>) -> ListColumn<T> {
-        let idx = match items.len() > 0 {
+        let idx = match !items.is_empty() {
             true => Some(0),
             false => None,
         };
@@ -149,207 +456,1694 @@ impl<T> From<Vec<T>> for ListColumn<T> {
 
 impl App {
     pub fn new() -> App {
-        App {
-            input: String::new(),
-            current_mode: Mode::Add,
-            current_column: Column::Todo,
-            todo: ListColumn::new(),
+        let mut app = App::from(Board::empty(""));
+        app.current_mode = Mode::Add;
+        app
+    }
+
+    // the board without its undo history, which is what each history entry holds
+    fn snapshot(&self) -> Board {
+        Board {
+            name: self.boards[self.current_board].name.clone(),
+            next_id: self.next_id,
+            columns: self.columns.iter().map(BoardColumn::from).collect(),
+            todo: vec![],
             wip: None,
-            done: ListColumn::new(),
+            done: vec![],
+            pomodoro: self.pomodoro.config.clone(),
+            keep_history: self.keep_history,
+            pull_top_priority: self.pull_top_priority,
+            archive_after: self.archive_after,
+            archive: self.archive.items.clone(),
+            history: None,
         }
     }
 
-    pub fn add_task(&mut self) {
-        if self.input.trim().is_empty() {
+    // records `before` as an undo step if the board actually changed since
+    fn commit(&mut self, before: Board) {
+        self.snap_to_filter();
+        if before == self.snapshot() {
             return;
         }
-        let cloned = self.input.clone();
-        self.todo.push(cloned);
-        self.input = String::new();
-        self.current_column = Column::Todo;
-        self.current_mode = Mode::Overview;
+        if self.read_only.is_some() {
+            self.replace_tasks(before);
+            self.report_read_only();
+            return;
+        }
+        let events = self.changes_since(&before);
+        self.journal.extend(events);
+        self.history.record(before);
+        self.revision += 1;
     }
 
-    pub fn move_to_done(&mut self) {
-        if let Some(task) = self.wip.take() {
-            self.done.push(task);
-            self.current_column = Column::Done;
-            self.current_mode = Mode::Overview;
+    // works out what happened to each task since `before`, for the journal
+    fn changes_since(&self, before: &Board) -> Vec<Event> {
+        let last = self.last_column();
+        let placed_before: HashMap<u64, (usize, &Task)> = before
+            .columns
+            .iter()
+            .enumerate()
+            .flat_map(|(column, saved)| {
+                saved
+                    .items
+                    .iter()
+                    .map(move |task| (task.id, (column, task)))
+            })
+            .collect();
+        let placed_after: HashMap<u64, (usize, &Task)> = self
+            .columns
+            .iter()
+            .enumerate()
+            .flat_map(|(column, current)| {
+                current
+                    .tasks
+                    .items
+                    .iter()
+                    .map(move |task| (task.id, (column, task)))
+            })
+            .collect();
+        let was_archived = |id: u64| before.archive.iter().any(|task| task.id == id);
+        let is_archived = |id: u64| self.archive.items.iter().any(|task| task.id == id);
+        let now = Local::now();
+        let board = &self.boards[self.current_board].name;
+        let event = |task: &Task, kind: EventKind, detail: Option<String>| Event {
+            at: now,
+            board: board.clone(),
+            task: task.id,
+            title: task.title.clone(),
+            kind,
+            detail,
+            from: None,
+            to: None,
+        };
+        let mut changes: Vec<Event> = vec![];
+        for (column, current) in self.columns.iter().enumerate() {
+            // the order of the tasks that stayed in this column, before and now
+            let stayed = |items: &[Task]| -> Vec<u64> {
+                items
+                    .iter()
+                    .map(|task| task.id)
+                    .filter(|id| {
+                        placed_before.get(id).map(|&(from, _)| from) == Some(column)
+                            && placed_after.get(id).map(|&(to, _)| to) == Some(column)
+                    })
+                    .collect()
+            };
+            let order_before = before
+                .columns
+                .get(column)
+                .map_or(vec![], |saved| stayed(&saved.items));
+            let order_after = stayed(&current.tasks.items);
+            for task in current.tasks.items.iter() {
+                let Some(&(from, old)) = placed_before.get(&task.id) else {
+                    let kind = if was_archived(task.id) {
+                        EventKind::Restored
+                    } else {
+                        EventKind::Created
+                    };
+                    changes.push(event(task, kind, Some(current.name.clone())));
+                    continue;
+                };
+                if from != column {
+                    let kind = if column == last {
+                        EventKind::Finished
+                    } else if current.wip {
+                        EventKind::Started
+                    } else if before.columns[from].wip && column < from {
+                        EventKind::Paused
+                    } else {
+                        EventKind::Moved
+                    };
+                    let detail = format!("{} -> {}", before.columns[from].name, current.name);
+                    changes.push(Event {
+                        from: Some(from),
+                        to: Some(column),
+                        ..event(task, kind, Some(detail))
+                    });
+                } else if order_before.iter().position(|&id| id == task.id)
+                    != order_after.iter().position(|&id| id == task.id)
+                {
+                    changes.push(event(
+                        task,
+                        EventKind::Reordered,
+                        Some(current.name.clone()),
+                    ));
+                }
+                let edited = edited_fields(old, task);
+                if !edited.is_empty() {
+                    changes.push(event(task, EventKind::Edited, Some(edited.join(", "))));
+                }
+            }
+        }
+        for saved in before.columns.iter() {
+            for task in saved
+                .items
+                .iter()
+                .filter(|task| !placed_after.contains_key(&task.id))
+            {
+                let kind = if is_archived(task.id) {
+                    EventKind::Archived
+                } else {
+                    EventKind::Deleted
+                };
+                changes.push(event(task, kind, None));
+            }
         }
+        changes
     }
 
-    pub fn on_down(&mut self) {
-        match self.current_column {
-            Column::Wip => {}
-            Column::Todo => self.todo.down(),
-            Column::Done => self.done.down(),
+    fn replace_tasks(&mut self, mut board: Board) {
+        let columns = board.take_columns();
+        if columns.len() == self.columns.len() {
+            for (column, saved) in self.columns.iter_mut().zip(columns) {
+                column.name = saved.name;
+                column.wip = saved.wip;
+                column.limit = saved.limit;
+                column.tasks.replace_items(saved.items);
+            }
+        } else {
+            self.columns = columns.into_iter().map(Column::from).collect();
+            self.current_column = self
+                .current_column
+                .min(self.columns.len().saturating_sub(1));
         }
+        self.next_id = board.next_id;
+        self.archive.replace_items(board.archive);
+        self.snap_to_filter();
     }
 
-    pub fn on_up(&mut self) {
-        match self.current_column {
-            Column::Wip => {}
-            Column::Todo => self.todo.up(),
-            Column::Done => self.done.up(),
+    // puts back a board from the undo history, journaled as undone or redone
+    // rather than as the moves and edits it takes to get there
+    fn restore(&mut self, board: Board, kind: EventKind) {
+        let before = self.snapshot();
+        self.replace_tasks(board);
+        let events = self.changes_since(&before);
+        self.journal
+            .extend(events.into_iter().map(|event| Event { kind, ..event }));
+        self.revision += 1;
+    }
+
+    /// Takes the boards as someone else left them on disk, keeping the cursor
+    /// where it was. This is not an undo step, nor a change that needs saving.
+    pub fn reload(&mut self, mut boards: Boards) {
+        if boards.boards.is_empty() {
+            boards.boards.push(Board::empty(""));
+        }
+        let name = self.boards[self.current_board].name.clone();
+        let index = boards.boards.iter().position(|board| board.name == name);
+        self.boards = boards.boards;
+        self.one_wip = boards.one_wip;
+        self.picked_board = self.picked_board.min(self.boards.len() - 1);
+        match index {
+            Some(index) => {
+                self.current_board = index;
+                self.reload_board(self.boards[index].clone());
+            }
+            // the board was removed or renamed, fall back to the first one
+            None => {
+                self.current_board = 0;
+                self.load_board(self.boards[0].clone());
+            }
         }
+        self.conflict = false;
+        self.leave_if_task_gone();
     }
 
-    pub fn on_left(&mut self) {
-        match self.current_column {
-            Column::Wip => self.current_column = Column::Todo,
-            Column::Done => self.current_column = Column::Wip,
-            _ => {}
+    // the task the current view works on
+    fn mode_task(&self) -> Option<u64> {
+        match self.current_mode {
+            Mode::Edit(id)
+            | Mode::Describe(id)
+            | Mode::Checklist(id)
+            | Mode::AddStep(id)
+            | Mode::Blockers(id) => Some(id),
+            _ => None,
         }
     }
 
-    pub fn on_right(&mut self) {
-        match self.current_column {
-            Column::Todo => self.current_column = Column::Wip,
-            Column::Wip => self.current_column = Column::Done,
-            _ => {}
+    // drops the current view when its task was removed from under it
+    fn leave_if_task_gone(&mut self) {
+        if let Some(id) = self.mode_task() {
+            if self.find_task(id).is_none() {
+                self.leave_removed_task();
+            }
         }
     }
 
-    pub fn on_move_up(&mut self) {
-        match self.current_column {
-            Column::Todo => self.todo.move_item_up(),
-            Column::Done => self.done.move_item_up(),
-            _ => {}
+    fn leave_removed_task(&mut self) {
+        self.report("The task was removed from the board");
+        self.input.clear();
+        self.current_mode = Mode::Overview;
+    }
+
+    fn reload_board(&mut self, board: Board) {
+        let pomodoro = board.pomodoro.clone();
+        let keep_history = board.keep_history;
+        let pull_top_priority = board.pull_top_priority;
+        let archive_after = board.archive_after;
+        self.replace_tasks(board);
+        self.pomodoro.config = pomodoro;
+        self.keep_history = keep_history;
+        self.pull_top_priority = pull_top_priority;
+        self.archive_after = archive_after;
+        // undoing past the reload would bring back what someone else just changed
+        self.history = History::default();
+        self.assign_missing_ids();
+        self.start_missing_clocks(Local::now());
+    }
+
+    fn load_board(&mut self, mut board: Board) {
+        self.columns = board.take_columns().into_iter().map(Column::from).collect();
+        self.current_column = 0;
+        self.next_id = board.next_id.max(1);
+        self.pomodoro.config = board.pomodoro;
+        self.keep_history = board.keep_history;
+        self.pull_top_priority = board.pull_top_priority;
+        self.archive = ListColumn::from(board.archive);
+        self.archive_after = board.archive_after;
+        self.history = board.history.unwrap_or_default();
+        self.assign_missing_ids();
+        self.start_missing_clocks(Local::now());
+        self.snap_to_filter();
+    }
+
+    pub fn board_name(&self) -> &str {
+        self.boards[self.current_board].name()
+    }
+
+    pub fn find_board(&self, name: &str) -> Option<usize> {
+        self.boards.iter().position(|board| board.name() == name)
+    }
+
+    /// The task in progress on the board at `index`.
+    pub fn board_wip_task(&self, index: usize) -> Option<&Task> {
+        if index == self.current_board {
+            self.wip_task()
+        } else {
+            self.boards[index].wip_task()
         }
     }
 
-    pub fn on_move_down(&mut self) {
-        match self.current_column {
-            Column::Todo => self.todo.move_item_down(),
-            Column::Done => self.done.move_item_down(),
-            _ => {}
+    /// Puts the current board away, undo history and all, and brings up the
+    /// one at `index`.
+    pub fn switch_board(&mut self, index: usize) {
+        if index == self.current_board || index >= self.boards.len() {
+            return;
         }
+        let mut current = self.snapshot();
+        current.history = Some(std::mem::take(&mut self.history));
+        self.boards[self.current_board] = current;
+        self.current_board = index;
+        self.load_board(self.boards[index].clone());
     }
 
-    pub fn on_move_right(&mut self) {
-        match self.current_column {
-            Column::Todo => {
-                if let None = self.wip {
-                    let task = self.todo.remove();
-                    self.wip = task;
-                    self.current_column = Column::Wip;
-                }
-            }
-            Column::Wip => {
-                if let Some(task) = self.wip.take() {
-                    self.done.push(task);
-                    self.current_column = Column::Done;
-                }
-            }
-            Column::Done => {}
+    // starts a board with the default columns, the caller makes sure the name is free
+    fn add_board(&mut self, name: &str) -> usize {
+        self.boards.push(Board::empty(name));
+        self.revision += 1;
+        self.boards.len() - 1
+    }
+
+    /// Switches to the board called `name`, starting it if there is none yet.
+    pub fn open_board(&mut self, name: &str) {
+        let index = match self.find_board(name) {
+            Some(index) => index,
+            None if self.report_read_only() => return,
+            None => self.add_board(name),
+        };
+        self.switch_board(index);
+    }
+
+    pub fn enter_board_picker(&mut self) {
+        self.picked_board = self.current_board;
+        self.current_mode = Mode::Boards;
+    }
+
+    pub fn leave_board_picker(&mut self) {
+        self.input.clear();
+        self.current_mode = Mode::Overview;
+    }
+
+    pub fn pick_next_board(&mut self) {
+        self.picked_board = (self.picked_board + 1).min(self.boards.len() - 1);
+    }
+
+    pub fn pick_previous_board(&mut self) {
+        self.picked_board = self.picked_board.saturating_sub(1);
+    }
+
+    pub fn pick_board(&mut self) {
+        self.switch_board(self.picked_board);
+        self.current_mode = Mode::Overview;
+    }
+
+    pub fn enter_add_board_mode(&mut self) {
+        if self.report_read_only() {
+            return;
         }
+        self.input.clear();
+        self.current_mode = Mode::AddBoard;
     }
 
-    pub fn on_move_left(&mut self) {
-        match self.current_column {
-            Column::Done => {
-                if let None = self.wip {
-                    let task = self.done.remove();
-                    self.wip = task;
-                    self.current_column = Column::Wip;
-                }
-            }
-            Column::Wip => {
-                if let Some(task) = self.wip.take() {
-                    self.todo.push(task);
-                    self.current_column = Column::Todo;
-                }
-            }
-            Column::Todo => {}
+    pub fn leave_add_board_mode(&mut self) {
+        self.input.clear();
+        self.current_mode = Mode::Boards;
+    }
+
+    pub fn add_board_from_input(&mut self) {
+        let name = self.input.trim().to_string();
+        if name.is_empty() {
+            self.report("Type a name for the board first");
+            return;
+        }
+        if self.find_board(&name).is_some() {
+            self.report(&format!("There already is a board called {}", name));
+            return;
         }
+        let index = self.add_board(&name);
+        self.switch_board(index);
+        self.input.clear();
+        self.current_mode = Mode::Overview;
     }
 
-    pub fn on_remove_task(&mut self) {
-        match self.current_column {
-            Column::Wip => {
-                self.wip = None;
-            }
-            Column::Done => {
-                self.done.remove();
-            }
-            Column::Todo => {
-                self.todo.remove();
-            }
+    pub fn on_conflict(&mut self) {
+        self.conflict = true;
+        self.status = Some("The board changed on disk: R to load it, O to keep yours".to_string());
+    }
+
+    pub fn resolve_conflict(&mut self) {
+        self.conflict = false;
+        self.status = None;
+    }
+
+    /// Keeps a failed save on screen until the board is written after all.
+    pub fn on_save_failed(&mut self, reason: &str) {
+        if !self.conflict {
+            self.status = Some(format!("{}, trying again", reason));
         }
     }
 
-    pub fn on_input(&mut self, char: char) {
-        self.input.push(char);
+    pub fn on_saved(&mut self) {
+        if !self.conflict {
+            self.status = None;
+        }
     }
 
-    pub fn on_backspace(&mut self) {
-        self.input.pop();
+    pub fn enter_read_only(&mut self, holder: Option<u32>) {
+        let holder = match holder {
+            Some(pid) => format!("another ow (pid {})", pid),
+            None => "another ow".to_string(),
+        };
+        self.read_only = Some(format!(
+            "Read only: the board is being edited by {}",
+            holder
+        ));
+        self.current_mode = Mode::Overview;
     }
 
-    pub fn on_cancel_input(&mut self) {
-        if self.input.trim().is_empty() {
+    /// Tells the user why an action did nothing.
+    pub fn report(&mut self, message: &str) {
+        self.messages.push(message.to_string());
+    }
+
+    // shows why nothing happened, returns whether the board is read only
+    fn report_read_only(&mut self) -> bool {
+        match self.read_only.clone() {
+            Some(reason) => {
+                self.report(&reason);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn undo(&mut self) {
+        if self.report_read_only() {
             return;
         }
-        self.current_mode = Mode::Overview;
-        self.input.clear();
+        let current = self.snapshot();
+        match self.history.undo(current) {
+            Some(board) => self.restore(board, EventKind::Undone),
+            None => self.report("Nothing to undo"),
+        }
     }
 
-    pub fn enter_focus(&mut self) {
-        if self.wip.is_some() {
-            self.current_mode = Mode::Focus;
+    pub fn redo(&mut self) {
+        if self.report_read_only() {
+            return;
+        }
+        let current = self.snapshot();
+        match self.history.redo(current) {
+            Some(board) => self.restore(board, EventKind::Redone),
+            None => self.report("Nothing to redo"),
         }
     }
 
-    pub fn leave_help(&mut self) {
-        self.current_mode = Mode::Overview;
+    fn tasks_mut(&mut self) -> impl Iterator<Item = &mut Task> {
+        self.columns
+            .iter_mut()
+            .flat_map(|column| column.tasks.items.iter_mut())
     }
 
-    pub fn enter_help(&mut self) {
-        self.current_mode = Mode::Help;
+    pub fn wip_column(&self) -> Option<usize> {
+        self.columns.iter().position(|column| column.wip)
     }
 
-    pub fn leave_focus(&mut self) {
-        self.current_mode = Mode::Overview;
-        self.input = String::new();
+    pub fn last_column(&self) -> usize {
+        self.columns.len().saturating_sub(1)
     }
 
-    pub fn enter_add_mode(&mut self) {
-        self.current_mode = Mode::Add;
+    /// The task being worked on, the one focus mode shows.
+    pub fn wip_task(&self) -> Option<&Task> {
+        let column = &self.columns[self.wip_column()?];
+        column.tasks.selected().or(column.tasks.items.first())
     }
 
-    pub fn enter_edit_mode(&mut self) {
-        match self.current_column {
-            Column::Todo => {
-                if let Some(task) = self.todo.current_item() {
-                    self.input = task.clone();
-                    self.current_mode = Mode::Edit(self.todo.index.unwrap());
-                }
+    fn wip_task_mut(&mut self) -> Option<&mut Task> {
+        let wip = self.wip_column()?;
+        let tasks = &mut self.columns[wip].tasks;
+        match tasks.index {
+            Some(_) => tasks.selected_mut(),
+            None => tasks.items.first_mut(),
+        }
+    }
+
+    pub fn current_tasks(&self) -> &ListColumn<Task> {
+        &self.columns[self.current_column].tasks
+    }
+
+    fn current_tasks_mut(&mut self) -> &mut ListColumn<Task> {
+        &mut self.columns[self.current_column].tasks
+    }
+
+    /// Tells whether one more task fits in column `to`, and if not, why.
+    pub fn check_limit(&self, to: usize) -> Result<(), String> {
+        let column = &self.columns[to];
+        if column.wip {
+            self.check_one_wip()?;
+        }
+        match column.limit() {
+            Some(limit) if column.is_full() => Err(format!(
+                "{} is at its limit of {} {}",
+                column.name,
+                limit,
+                if limit == 1 { "task" } else { "tasks" }
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Tells whether the selected task of the current column may go into
+    /// column `to`, which it may not while it waits on tasks that are not done.
+    pub fn check_blocked(&self, to: usize) -> Result<(), String> {
+        let Some(task) = self.current_tasks().selected() else {
+            return Ok(());
+        };
+        if !self.columns[to].wip {
+            return Ok(());
+        }
+        match self.blockers(task).first() {
+            Some(blocker) => Err(format!(
+                "'{}' is waiting on #{} {}",
+                task.title, blocker.id, blocker.title
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// A task can only wait on other tasks that are on the board, and not on
+    /// one that waits on it in turn.
+    pub fn check_blocked_by(&self, id: u64, blocked_by: &[u64]) -> Result<(), String> {
+        for &blocker in blocked_by {
+            if blocker == id {
+                return Err("A task cannot wait on itself".to_string());
             }
-            Column::Wip => {
-                if let Some(task) = &self.wip {
-                    self.input = task.clone();
-                    self.current_mode = Mode::Edit(0);
-                }
+            if self.find_task(blocker).is_none() {
+                return Err(format!("There is no task #{} to wait on", blocker));
             }
-            Column::Done => {
-                if let Some(task) = self.done.current_item() {
-                    self.input = task.clone();
-                    self.current_mode = Mode::Edit(self.done.index.unwrap());
-                }
+            if self.waits_on(blocker, id) {
+                return Err(format!("#{} already waits on this task", blocker));
             }
         }
+        Ok(())
     }
 
-    pub fn edit_task(&mut self, index: usize) {
-        if !self.input.trim().is_empty() {
-            let value = self.input.clone();
-            match self.current_column {
-                Column::Todo => self.todo.replace_at(index, value),
-                Column::Done => self.done.replace_at(index, value),
-                Column::Wip => self.wip = Some(value),
-            };
-            self.input = String::new();
-            self.current_mode = Mode::Overview;
+    // whether task `from` waits on task `id`, directly or through the tasks it waits on
+    fn waits_on(&self, from: u64, id: u64) -> bool {
+        let mut seen = vec![];
+        let mut pending = vec![from];
+        while let Some(next) = pending.pop() {
+            if next == id {
+                return true;
+            }
+            if seen.contains(&next) {
+                continue;
+            }
+            seen.push(next);
+            if let Some((_, task)) = self.find_task(next) {
+                pending.extend(task.blocked_by.iter().copied());
+            }
+        }
+        false
+    }
+
+    /// The column a task is in and the task itself.
+    pub fn find_task(&self, id: u64) -> Option<(usize, &Task)> {
+        self.columns.iter().enumerate().find_map(|(index, column)| {
+            let task = column.tasks.items.iter().find(|task| task.id == id)?;
+            Some((index, task))
+        })
+    }
+
+    fn find_task_mut(&mut self, id: u64) -> Option<&mut Task> {
+        self.tasks_mut().find(|task| task.id == id)
+    }
+
+    /// The tasks `task` waits on that are not done yet. Ids of tasks that were
+    /// removed from the board don't hold anything up.
+    pub fn blockers(&self, task: &Task) -> Vec<&Task> {
+        if task.finished_at.is_some() {
+            return vec![];
+        }
+        task.blocked_by
+            .iter()
+            .filter_map(|&id| self.find_task(id))
+            .filter(|&(column, _)| column != self.last_column())
+            .map(|(_, blocker)| blocker)
+            .collect()
+    }
+
+    pub fn is_blocked(&self, task: &Task) -> bool {
+        !self.blockers(task).is_empty()
+    }
+
+    pub fn enter_blockers(&mut self) {
+        let selected = self.current_tasks().selected();
+        match selected.map(|task| (task.id, task.blocked_by.is_empty())) {
+            Some((_, true)) => {
+                self.report("The task does not wait on anything, after:<id> in e sets it")
+            }
+            Some((id, false)) => self.current_mode = Mode::Blockers(id),
+            None => self.report("There is no task to show the blockers of"),
+        }
+    }
+
+    pub fn leave_blockers(&mut self) {
+        self.current_mode = Mode::Overview;
+    }
+
+    // with one_wip set, a task in progress on any other board keeps wip closed here
+    fn check_one_wip(&self) -> Result<(), String> {
+        if !self.one_wip {
+            return Ok(());
+        }
+        let busy = self
+            .boards
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| index != self.current_board)
+            .find_map(|(_, board)| Some((board.name(), board.wip_task()?)));
+        match busy {
+            Some((board, task)) => Err(format!("'{}' is already in progress on {}", task, board)),
+            None => Ok(()),
+        }
+    }
+
+    // moves the selected task of the current column to column `to` and follows it there,
+    // as long as that keeps `to` within its limit
+    fn move_task(&mut self, to: usize) {
+        let from = self.current_column;
+        if self.current_tasks().selected().is_none() {
+            self.report(&format!(
+                "There is no task in {} to move",
+                self.columns[from].name
+            ));
+            return;
+        }
+        if to == from {
+            return;
+        }
+        if to >= self.columns.len() {
+            self.report(&format!("{} is the last column", self.columns[from].name));
+            return;
+        }
+        if let Err(reason) = self.check_limit(to).and(self.check_blocked(to)) {
+            self.report(&reason);
+            return;
         }
+        let Some(mut task) = self.current_tasks_mut().remove() else {
+            return;
+        };
+        let now = Local::now();
+        if self.columns[from].wip {
+            task.pause(now);
+        }
+        if self.columns[to].wip {
+            task.start(now);
+        }
+        if to == self.last_column() {
+            task.finish(now);
+        } else if from == self.last_column() {
+            task.finished_at = None;
+        }
+        self.columns[to].tasks.push(task);
+        self.current_column = to;
+    }
+
+    // tasks coming from boards saved before ids existed have id 0
+    fn assign_missing_ids(&mut self) {
+        let highest = self.tasks_mut().map(|task| task.id).max().unwrap_or(0);
+        let mut next_id = self.next_id.max(highest + 1);
+        for task in self.tasks_mut().filter(|task| task.id == 0) {
+            task.id = next_id;
+            next_id += 1;
+        }
+        self.next_id = next_id;
+    }
+
+    // boards saved before time was tracked have a task in wip without a running clock
+    fn start_missing_clocks(&mut self, now: DateTime<Local>) {
+        let Some(wip) = self.wip_column() else {
+            return;
+        };
+        for task in self.columns[wip]
+            .tasks
+            .items
+            .iter_mut()
+            .filter(|task| task.wip_since.is_none())
+        {
+            task.start(now);
+        }
+    }
+
+    fn take_next_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    pub fn add_task(&mut self) {
+        let input = match parse_input(&self.input, Local::now()) {
+            Ok(input) => input,
+            Err(reason) => return self.report(&reason),
+        };
+        if input.title.is_empty() {
+            self.report("Type a title for the task first");
+            return;
+        }
+        if let Err(reason) = self
+            .check_limit(0)
+            .and(self.check_blocked_by(self.next_id, &input.blocked_by))
+        {
+            self.report(&reason);
+            return;
+        }
+        let before = self.snapshot();
+        let id = self.take_next_id();
+        let mut task = Task::new(id, input.title);
+        task.tags = input.tags;
+        task.due = input.due;
+        task.blocked_by = input.blocked_by;
+        if !self.is_visible(&task) {
+            self.report("The new task is hidden by the filter, T shows everything");
+        }
+        self.columns[0].tasks.push(task);
+        self.input = String::new();
+        self.current_column = 0;
+        self.current_mode = Mode::Overview;
+        self.commit(before);
+    }
+
+    pub fn move_to_done(&mut self) {
+        let before = self.snapshot();
+        match self.wip_column() {
+            Some(wip) => {
+                self.current_column = wip;
+                self.move_task(self.last_column());
+                self.current_mode = Mode::Overview;
+            }
+            None => self.report(NO_WIP_COLUMN),
+        }
+        self.commit(before);
+    }
+
+    pub fn move_to_wip(&mut self) {
+        let before = self.snapshot();
+        match self.wip_column() {
+            Some(wip) => self.move_task(wip),
+            None => self.report(NO_WIP_COLUMN),
+        }
+        self.commit(before);
+    }
+
+    /// Brings the selected task into wip right away. If wip is full, the task
+    /// being worked on makes room and is parked at the top of the column the
+    /// new one came from, with the interruption counted against it.
+    pub fn swap_into_wip(&mut self) {
+        let Some(wip) = self.wip_column() else {
+            self.report(NO_WIP_COLUMN);
+            return;
+        };
+        let from = self.current_column;
+        if from >= wip {
+            self.report(&format!(
+                "Only tasks left of {} can be swapped in",
+                self.columns[wip].name
+            ));
+            return;
+        }
+        if self.current_tasks().selected().is_none() {
+            self.report(&format!(
+                "There is no task in {} to swap in",
+                self.columns[from].name
+            ));
+            return;
+        }
+        if let Err(reason) = self.check_one_wip().and(self.check_blocked(wip)) {
+            self.report(&reason);
+            return;
+        }
+        let before = self.snapshot();
+        let parked = if self.columns[wip].is_full() {
+            // the task being worked on, the same one wip_task picks
+            let tasks = &mut self.columns[wip].tasks;
+            tasks.index = tasks.index.or(Some(0));
+            tasks.remove()
+        } else {
+            None
+        };
+        self.move_task(wip);
+        if let Some(mut task) = parked {
+            task.interrupt(Local::now());
+            self.columns[from].tasks.insert(0, task);
+        }
+        self.commit(before);
+    }
+
+    pub fn enter_search_mode(&mut self) {
+        self.input.clear();
+        self.current_mode = Mode::Search;
+    }
+
+    /// What task titles are matched against, while typing a search or after it.
+    pub fn search_query(&self) -> Option<&str> {
+        match self.current_mode {
+            Mode::Search => Some(&self.input),
+            _ => self.search.as_deref(),
+        }
+    }
+
+    pub fn cancel_search(&mut self) {
+        self.search = None;
+        self.input.clear();
+        self.current_mode = Mode::Overview;
+    }
+
+    pub fn on_search(&mut self) {
+        let query = self.input.trim().to_string();
+        if query.is_empty() {
+            self.cancel_search();
+            return;
+        }
+        self.search = Some(query);
+        self.input.clear();
+        self.current_mode = Mode::Overview;
+        self.jump_to_match(true, true);
+    }
+
+    pub fn next_match(&mut self) {
+        self.jump_to_match(true, false);
+    }
+
+    pub fn previous_match(&mut self) {
+        self.jump_to_match(false, false);
+    }
+
+    // every task the search matches as (column, index), in reading order
+    fn search_matches(&self) -> Vec<(usize, usize)> {
+        let Some(query) = self.search_query() else {
+            return vec![];
+        };
+        self.columns
+            .iter()
+            .enumerate()
+            .flat_map(|(column, tasks)| {
+                tasks
+                    .tasks
+                    .items
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, task)| self.is_visible(task))
+                    .filter(|(_, task)| fuzzy_match(query, &task.title).is_some())
+                    .map(move |(index, _)| (column, index))
+            })
+            .collect()
+    }
+
+    // moves the cursor to the next match after it, or before it going backwards,
+    // wrapping around the board like vim does
+    fn jump_to_match(&mut self, forward: bool, include_current: bool) {
+        let Some(query) = self.search.clone() else {
+            self.report("Press / to search first");
+            return;
+        };
+        let matches = self.search_matches();
+        let cursor = (self.current_column, self.current_tasks().index.unwrap_or(0));
+        let found = if forward {
+            matches
+                .iter()
+                .find(|&&position| position > cursor || include_current && position == cursor)
+        } else {
+            matches.iter().rev().find(|&&position| position < cursor)
+        };
+        let target = match found {
+            Some(&target) => target,
+            None => {
+                let wrapped = if forward {
+                    matches.first()
+                } else {
+                    matches.last()
+                };
+                let Some(&target) = wrapped else {
+                    self.report(&format!("No task matches '{}'", query));
+                    return;
+                };
+                if target != cursor {
+                    self.report(if forward {
+                        "Search hit the end of the board, continuing at the start"
+                    } else {
+                        "Search hit the start of the board, continuing at the end"
+                    });
+                }
+                target
+            }
+        };
+        let (column, index) = target;
+        self.current_column = column;
+        self.columns[column].tasks.index = Some(index);
+    }
+
+    pub fn enter_filter_mode(&mut self) {
+        self.input = self.filter.clone().unwrap_or_default();
+        self.current_mode = Mode::Filter;
+    }
+
+    pub fn leave_filter_mode(&mut self) {
+        self.input.clear();
+        self.current_mode = Mode::Overview;
+    }
+
+    /// Narrows the board to the tasks tagged with what was typed, or shows
+    /// everything again if nothing was.
+    pub fn apply_filter(&mut self) {
+        let tag = self.input.trim().trim_start_matches('#').to_string();
+        self.filter = if tag.is_empty() { None } else { Some(tag) };
+        self.leave_filter_mode();
+        self.snap_to_filter();
+    }
+
+    pub fn clear_filter(&mut self) {
+        if self.filter.take().is_none() {
+            self.report("The board is not filtered");
+        }
+    }
+
+    pub fn is_visible(&self, task: &Task) -> bool {
+        match &self.filter {
+            Some(tag) => task.has_tag(tag),
+            None => true,
+        }
+    }
+
+    // the closest task after `index` in the current column, or before it, that the filter shows
+    fn visible_neighbour(&self, index: usize, forward: bool) -> Option<usize> {
+        let items = &self.current_tasks().items;
+        if forward {
+            (index + 1..items.len()).find(|&i| self.is_visible(&items[i]))
+        } else {
+            (0..index).rev().find(|&i| self.is_visible(&items[i]))
+        }
+    }
+
+    // keeps every cursor on a task the filter shows, so nothing hidden gets moved or deleted
+    fn snap_to_filter(&mut self) {
+        if self.filter.is_none() {
+            return;
+        }
+        for column in 0..self.columns.len() {
+            let tasks = &self.columns[column].tasks;
+            let index = tasks.index.unwrap_or(0);
+            let visible = |i: &usize| self.is_visible(&tasks.items[*i]);
+            let snapped = (index..tasks.items.len())
+                .find(visible)
+                .or_else(|| (0..index.min(tasks.items.len())).rev().find(visible));
+            self.columns[column].tasks.index = snapped;
+        }
+    }
+
+    pub fn on_down(&mut self) {
+        if self.filter.is_none() {
+            self.current_tasks_mut().down();
+            return;
+        }
+        let next = match self.current_tasks().index {
+            Some(index) => self.visible_neighbour(index, true),
+            None => None,
+        };
+        if next.is_some() {
+            self.current_tasks_mut().index = next;
+        }
+    }
+
+    pub fn on_up(&mut self) {
+        if self.filter.is_none() {
+            self.current_tasks_mut().up();
+            return;
+        }
+        let previous = match self.current_tasks().index {
+            Some(index) => self.visible_neighbour(index, false),
+            None => None,
+        };
+        if previous.is_some() {
+            self.current_tasks_mut().index = previous;
+        }
+    }
+
+    pub fn on_left(&mut self) {
+        self.current_column = self.current_column.saturating_sub(1);
+    }
+
+    pub fn on_right(&mut self) {
+        self.current_column = (self.current_column + 1).min(self.last_column());
+    }
+
+    pub fn on_move_up(&mut self) {
+        let Some(index) = self.current_tasks().index else {
+            self.report("There is no task to move");
+            return;
+        };
+        match self.visible_neighbour(index, false) {
+            None => self.report("The task is already at the top"),
+            Some(to) => {
+                let before = self.snapshot();
+                self.current_tasks_mut().move_item_to(to);
+                self.commit(before);
+            }
+        }
+    }
+
+    pub fn on_move_down(&mut self) {
+        let Some(index) = self.current_tasks().index else {
+            self.report("There is no task to move");
+            return;
+        };
+        match self.visible_neighbour(index, true) {
+            None => self.report("The task is already at the bottom"),
+            Some(to) => {
+                let before = self.snapshot();
+                self.current_tasks_mut().move_item_to(to);
+                self.commit(before);
+            }
+        }
+    }
+
+    pub fn on_move_right(&mut self) {
+        let before = self.snapshot();
+        let to = self.current_column + 1;
+        if self.pull_top_priority
+            && self.wip_column() == Some(to)
+            && self.current_tasks().selected().is_none()
+        {
+            self.current_tasks_mut().index = self.top_priority(self.current_column);
+        }
+        self.move_task(to);
+        self.commit(before);
+    }
+
+    /// The most urgent task the filter shows in `column`, the first one among equals.
+    pub fn top_priority(&self, column: usize) -> Option<usize> {
+        let items = &self.columns[column].tasks.items;
+        (0..items.len())
+            .filter(|&index| self.is_visible(&items[index]))
+            .min_by_key(|&index| items[index].priority_rank())
+    }
+
+    pub fn raise_priority(&mut self) {
+        self.change_priority(true);
+    }
+
+    pub fn lower_priority(&mut self) {
+        self.change_priority(false);
+    }
+
+    fn change_priority(&mut self, raise: bool) {
+        let Some(task) = self.current_tasks().selected() else {
+            self.report("There is no task to prioritize");
+            return;
+        };
+        let priority = match (task.priority, raise) {
+            (Some(0), true) => {
+                self.report("The task already has the highest priority");
+                return;
+            }
+            (None, false) => {
+                self.report("The task has no priority to lower");
+                return;
+            }
+            (Some(priority), true) => Some(priority - 1),
+            (None, true) => Some(LOWEST_PRIORITY),
+            (Some(LOWEST_PRIORITY), false) => None,
+            (Some(priority), false) => Some(priority + 1),
+        };
+        let before = self.snapshot();
+        if let Some(task) = self.current_tasks_mut().selected_mut() {
+            task.priority = priority;
+        }
+        self.commit(before);
+    }
+
+    /// Orders the current column by priority, keeping the order of tasks
+    /// with the same priority and the cursor on the task it was on.
+    pub fn sort_by_priority(&mut self) {
+        let before = self.snapshot();
+        let tasks = self.current_tasks_mut();
+        let selected = tasks.selected().map(|task| task.id);
+        tasks.items.sort_by_key(Task::priority_rank);
+        if let Some(id) = selected {
+            tasks.index = tasks.items.iter().position(|task| task.id == id);
+        }
+        if before == self.snapshot() {
+            self.report("The tasks are already sorted by priority");
+            return;
+        }
+        self.commit(before);
+    }
+
+    pub fn on_move_left(&mut self) {
+        if self.current_column == 0 {
+            self.report(&format!("{} is the first column", self.columns[0].name));
+            return;
+        }
+        let before = self.snapshot();
+        self.move_task(self.current_column - 1);
+        self.commit(before);
+    }
+
+    pub fn on_remove_task(&mut self) {
+        if self.current_tasks().selected().is_none() {
+            self.report("There is no task to delete");
+            return;
+        }
+        let before = self.snapshot();
+        self.current_tasks_mut().remove();
+        self.commit(before);
+    }
+
+    pub fn on_input(&mut self, char: char) {
+        self.input.push(char);
+    }
+
+    pub fn on_backspace(&mut self) {
+        self.input.pop();
+    }
+
+    pub fn on_cancel_input(&mut self) {
+        if self.input.trim().is_empty() {
+            return;
+        }
+        self.current_mode = Mode::Overview;
+        self.input.clear();
+    }
+
+    pub fn on_tick(&mut self) {
+        self.messages.tick(Instant::now());
+        // other modes hold on to the position of a task, which must not move under them
+        if let Mode::Overview = self.current_mode {
+            self.roll_over(Local::now());
+        }
+        if let Some(ended) = self.pomodoro.tick(Instant::now()) {
+            // a change of its own, so undo takes the count back only when asked to
+            if ended == Phase::Work && self.read_only.is_none() {
+                let before = self.snapshot();
+                if let Some(task) = self.wip_task_mut() {
+                    task.pomodoros += 1;
+                }
+                self.commit(before);
+            }
+            self.bell = true;
+        }
+    }
+
+    pub fn toggle_pomodoro(&mut self) {
+        self.pomodoro.toggle(Instant::now());
+    }
+
+    pub fn reset_pomodoro(&mut self) {
+        self.pomodoro.reset();
+    }
+
+    pub fn enter_focus(&mut self) {
+        match self.wip_column() {
+            Some(_) if self.wip_task().is_some() => self.current_mode = Mode::Focus,
+            Some(wip) => self.report(&format!(
+                "Move a task to {} before focusing on it",
+                self.columns[wip].name
+            )),
+            None => self.report(NO_WIP_COLUMN),
+        }
+    }
+
+    pub fn leave_help(&mut self) {
+        self.current_mode = Mode::Overview;
+    }
+
+    pub fn enter_help(&mut self) {
+        self.current_mode = Mode::Help;
+    }
+
+    pub fn leave_focus(&mut self) {
+        self.current_mode = Mode::Overview;
+        self.input = String::new();
+    }
+
+    pub fn enter_add_mode(&mut self) {
+        if self.report_read_only() {
+            return;
+        }
+        self.current_mode = Mode::Add;
+    }
+
+    pub fn enter_edit_mode(&mut self) {
+        if self.report_read_only() {
+            return;
+        }
+        if let Some(task) = self.current_tasks().selected() {
+            let id = task.id;
+            self.input = task.to_input();
+            self.current_mode = Mode::Edit(id);
+        } else {
+            self.report("There is no task to edit");
+        }
+    }
+
+    pub fn toggle_details(&mut self) {
+        self.show_details = !self.show_details;
+    }
+
+    pub fn enter_describe_mode(&mut self) {
+        if self.report_read_only() {
+            return;
+        }
+        if let Some(task) = self.current_tasks().selected() {
+            let id = task.id;
+            self.input = task.description.clone().unwrap_or_default();
+            self.current_mode = Mode::Describe(id);
+        } else {
+            self.report("There is no task to describe");
+        }
+    }
+
+    pub fn on_newline(&mut self) {
+        self.input.push('\n');
+    }
+
+    pub fn describe_task(&mut self, id: u64) {
+        let description = self.input.trim_end().to_string();
+        let before = self.snapshot();
+        let Some(task) = self.find_task_mut(id) else {
+            return self.leave_removed_task();
+        };
+        task.description = if description.is_empty() {
+            None
+        } else {
+            Some(description)
+        };
+        self.commit(before);
+        self.input = String::new();
+        self.current_mode = Mode::Overview;
+    }
+
+    pub fn enter_checklist(&mut self) {
+        match self.current_tasks().selected().map(|task| task.id) {
+            Some(id) => {
+                self.step_index = 0;
+                self.current_mode = Mode::Checklist(id);
+            }
+            None => self.report("There is no task to open the checklist of"),
+        }
+    }
+
+    pub fn leave_checklist(&mut self) {
+        self.current_mode = Mode::Overview;
+    }
+
+    pub fn checklist_down(&mut self, id: u64) {
+        let Some((_, task)) = self.find_task(id) else {
+            return self.leave_removed_task();
+        };
+        let steps = task.checklist.len();
+        self.step_index = (self.step_index + 1).min(steps.saturating_sub(1));
+    }
+
+    pub fn checklist_up(&mut self) {
+        self.step_index = self.step_index.saturating_sub(1);
+    }
+
+    pub fn toggle_step(&mut self, id: u64) {
+        let step = self.step_index;
+        let before = self.snapshot();
+        let Some(task) = self.find_task_mut(id) else {
+            return self.leave_removed_task();
+        };
+        match task.checklist.get_mut(step) {
+            Some(step) => step.done = !step.done,
+            None => self.report("The checklist is empty, a adds a step"),
+        }
+        self.commit(before);
+    }
+
+    pub fn remove_step(&mut self, id: u64) {
+        let step = self.step_index;
+        let before = self.snapshot();
+        let Some(task) = self.find_task_mut(id) else {
+            return self.leave_removed_task();
+        };
+        let checklist = &mut task.checklist;
+        if step < checklist.len() {
+            checklist.remove(step);
+            self.step_index = step.min(checklist.len().saturating_sub(1));
+        } else {
+            self.report("There is no step to delete");
+        }
+        self.commit(before);
+    }
+
+    pub fn enter_add_step_mode(&mut self, id: u64) {
+        if self.report_read_only() {
+            return;
+        }
+        self.input.clear();
+        self.current_mode = Mode::AddStep(id);
+    }
+
+    pub fn leave_add_step_mode(&mut self, id: u64) {
+        self.input.clear();
+        self.current_mode = Mode::Checklist(id);
+    }
+
+    pub fn add_step(&mut self, id: u64) {
+        let text = self.input.trim().to_string();
+        if text.is_empty() {
+            self.report("Type the step first");
+            return;
+        }
+        let before = self.snapshot();
+        let Some(task) = self.find_task_mut(id) else {
+            return self.leave_removed_task();
+        };
+        let checklist = &mut task.checklist;
+        checklist.push(Step { text, done: false });
+        self.step_index = checklist.len() - 1;
+        self.commit(before);
+        self.leave_add_step_mode(id);
+    }
+
+    /// Ticks off the next open step of the wip task, from focus mode.
+    pub fn check_next_step(&mut self) {
+        let before = self.snapshot();
+        let Some(task) = self.wip_task_mut() else {
+            return;
+        };
+        match task.next_step() {
+            Some(step) => task.checklist[step].done = true,
+            None => self.report("There is no step left to check"),
+        }
+        self.commit(before);
+    }
+
+    /// Tells whether the selected task can go to the archive, which only
+    /// takes tasks that are done.
+    pub fn check_archive(&self) -> Result<(), String> {
+        let last = &self.columns[self.last_column()];
+        if self.current_column != self.last_column() {
+            return Err(format!("Only tasks in {} can be archived", last.name));
+        }
+        match last.tasks.selected() {
+            Some(_) => Ok(()),
+            None => Err(format!("There is no task in {} to archive", last.name)),
+        }
+    }
+
+    pub fn archive_task(&mut self) {
+        if let Err(reason) = self.check_archive() {
+            self.report(&reason);
+            return;
+        }
+        let before = self.snapshot();
+        if let Some(task) = self.current_tasks_mut().remove() {
+            self.archive.insert(0, task);
+            self.archive.index = Some(0);
+        }
+        self.commit(before);
+    }
+
+    /// Moves the tasks that have been done for long enough to the archive, as
+    /// set by `archive_after`.
+    pub fn roll_over(&mut self, now: DateTime<Local>) {
+        let Some(rollover) = self.archive_after else {
+            return;
+        };
+        let last = self.last_column();
+        let is_due = |task: &Task| {
+            task.finished_at
+                .is_some_and(|finished_at| rollover.is_due(finished_at, now))
+        };
+        if self.read_only.is_some() || !self.columns[last].tasks.items.iter().any(is_due) {
+            return;
+        }
+        let before = self.snapshot();
+        let (due, kept) = std::mem::take(&mut self.columns[last].tasks.items)
+            .into_iter()
+            .partition::<Vec<Task>, _>(is_due);
+        self.columns[last].tasks.replace_items(kept);
+        self.report(&format!(
+            "Archived {} finished {}, Z shows the archive",
+            due.len(),
+            if due.len() == 1 { "task" } else { "tasks" }
+        ));
+        for task in due {
+            self.archive.insert(0, task);
+        }
+        self.commit(before);
+    }
+
+    pub fn enter_archive(&mut self) {
+        if self.archive.items.is_empty() {
+            self.report("The archive is empty, z archives a finished task");
+            return;
+        }
+        self.current_mode = Mode::Archive;
+    }
+
+    pub fn leave_archive(&mut self) {
+        self.current_mode = Mode::Overview;
+    }
+
+    /// Puts the selected archived task back into the first column, to be done again.
+    pub fn restore_archived(&mut self) {
+        if self.archive.selected().is_none() {
+            return;
+        }
+        if let Err(reason) = self.check_limit(0) {
+            self.report(&reason);
+            return;
+        }
+        let before = self.snapshot();
+        if let Some(mut task) = self.archive.remove() {
+            task.finished_at = None;
+            self.report(&format!("'{}' is back in {}", task, self.columns[0].name));
+            self.columns[0].tasks.push(task);
+        }
+        self.commit(before);
+    }
+
+    pub fn remove_archived(&mut self) {
+        let before = self.snapshot();
+        self.archive.remove();
+        self.commit(before);
+    }
+
+    /// Opens the log view on the journal entries of the selected task.
+    pub fn enter_log(&mut self, journal: Vec<Event>) {
+        let Some(task) = self.current_tasks().selected() else {
+            self.report("There is no task to show the log of");
+            return;
+        };
+        let board = &self.boards[self.current_board].name;
+        let entries: Vec<Event> = journal
+            .into_iter()
+            .filter(|event| event.task == task.id && &event.board == board)
+            .collect();
+        if entries.is_empty() {
+            self.report("Nothing has been logged for this task yet");
+            return;
+        }
+        self.log = ListColumn::from(entries);
+        // the latest entry is the one to start from
+        self.log.index = self.log.items.len().checked_sub(1);
+        self.current_mode = Mode::Log;
+    }
+
+    pub fn leave_log(&mut self) {
+        self.current_mode = Mode::Overview;
+    }
+
+    pub fn enter_stats(&mut self, journal: Vec<Event>) {
+        self.stats_journal = journal;
+        self.current_mode = Mode::Stats;
+    }
+
+    pub fn leave_stats(&mut self) {
+        self.stats_journal = vec![];
+        self.current_mode = Mode::Overview;
+    }
+
+    /// How the current board went since `since`, counting the finished tasks
+    /// still on it and the ones in its archive.
+    pub fn build_report(&self, journal: &[Event], since: DateTime<Local>) -> Report {
+        let board = &self.boards[self.current_board].name;
+        let journal: Vec<Event> = journal
+            .iter()
+            .filter(|event| &event.board == board)
+            .cloned()
+            .collect();
+        let tasks = self.columns[self.last_column()]
+            .tasks
+            .items
+            .iter()
+            .chain(self.archive.items.iter());
+        Report::new(tasks, &journal, since, Local::now())
+    }
+
+    pub fn edit_task(&mut self, id: u64) {
+        let input = match parse_input(&self.input, Local::now()) {
+            Ok(input) => input,
+            Err(reason) => return self.report(&reason),
+        };
+        if self.find_task(id).is_none() {
+            self.leave_removed_task();
+        } else if input.title.is_empty() {
+            self.report("A task needs a title");
+        } else if let Err(reason) = self.check_blocked_by(id, &input.blocked_by) {
+            self.report(&reason);
+        } else {
+            let before = self.snapshot();
+            if let Some(task) = self.find_task_mut(id) {
+                task.title = input.title;
+                task.tags = input.tags;
+                task.due = input.due;
+                task.blocked_by = input.blocked_by;
+            }
+            self.commit(before);
+            self.input = String::new();
+            self.current_mode = Mode::Overview;
+        }
+    }
+}
+
+// the fields someone changed on a task, leaving out the ones that move along
+// with the task as it is worked on
+fn edited_fields(before: &Task, after: &Task) -> Vec<&'static str> {
+    let mut fields = vec![];
+    if before.title != after.title {
+        fields.push("title");
+    }
+    if before.tags != after.tags {
+        fields.push("tags");
+    }
+    if before.due != after.due {
+        fields.push("due");
+    }
+    if before.priority != after.priority {
+        fields.push("priority");
+    }
+    if before.description != after.description {
+        fields.push("notes");
+    }
+    if before.checklist != after.checklist {
+        fields.push("checklist");
+    }
+    if before.blocked_by != after.blocked_by {
+        fields.push("blocked by");
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLUMNS: &str = "
+columns:
+- name: Backlog
+- name: Todo
+- name: Doing
+  wip: true
+- name: Review
+- name: Done
+";
+
+    fn board(titles: &[&str]) -> App {
+        let mut app = App::from(serde_yaml::from_str::<Board>(COLUMNS).unwrap());
+        for title in titles {
+            app.input = title.to_string();
+            app.add_task();
+        }
+        app.journal.clear();
+        app
+    }
+
+    // what was journaled since the last call, as kind and detail
+    fn logged(app: &mut App) -> Vec<(EventKind, Option<String>)> {
+        std::mem::take(&mut app.journal)
+            .into_iter()
+            .map(|event| (event.kind, event.detail))
+            .collect()
+    }
+
+    fn entry(kind: EventKind, detail: &str) -> (EventKind, Option<String>) {
+        (kind, Some(detail.to_string()))
+    }
+
+    #[test]
+    fn adding_a_task_logs_it_as_created() {
+        let mut app = board(&[]);
+        app.input = "write tests".to_string();
+        app.add_task();
+        assert_eq!(logged(&mut app), vec![entry(EventKind::Created, "Backlog")]);
+    }
+
+    #[test]
+    fn moving_right_logs_moved_started_and_finished() {
+        let mut app = board(&["write tests"]);
+        let moves = [
+            entry(EventKind::Moved, "Backlog -> Todo"),
+            entry(EventKind::Started, "Todo -> Doing"),
+            // moving on to review is not a pause
+            entry(EventKind::Moved, "Doing -> Review"),
+            entry(EventKind::Finished, "Review -> Done"),
+        ];
+        for expected in moves {
+            app.on_move_right();
+            assert_eq!(logged(&mut app), vec![expected]);
+        }
+    }
+
+    #[test]
+    fn moving_back_out_of_wip_logs_a_pause() {
+        let mut app = board(&["write tests"]);
+        app.on_move_right();
+        app.on_move_right();
+        app.journal.clear();
+        app.on_move_left();
+        let events = std::mem::take(&mut app.journal);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, EventKind::Paused);
+        assert_eq!((events[0].from, events[0].to), (Some(2), Some(1)));
+        assert!(events[0].is_context_switch());
+    }
+
+    #[test]
+    fn reordering_logs_the_tasks_that_changed_places() {
+        let mut app = board(&["first", "second", "third"]);
+        // the cursor is on the last task added
+        app.on_move_up();
+        let events = std::mem::take(&mut app.journal);
+        let titles: Vec<&str> = events.iter().map(|event| event.title.as_str()).collect();
+        assert!(events
+            .iter()
+            .all(|event| event.kind == EventKind::Reordered));
+        assert_eq!(titles, vec!["third", "second"]);
+    }
+
+    #[test]
+    fn editing_logs_the_fields_that_changed() {
+        let mut app = board(&["write tests"]);
+        app.enter_edit_mode();
+        let Mode::Edit(id) = app.current_mode else {
+            panic!("the task should be open for editing");
+        };
+        app.input = "write more tests #testing".to_string();
+        app.edit_task(id);
+        assert_eq!(
+            logged(&mut app),
+            vec![entry(EventKind::Edited, "title, tags")]
+        );
+    }
+
+    #[test]
+    fn archiving_and_deleting_are_told_apart() {
+        let mut app = board(&["archive me", "delete me"]);
+        app.on_remove_task();
+        assert_eq!(logged(&mut app), vec![(EventKind::Deleted, None)]);
+        for _ in 0..4 {
+            app.on_move_right();
+        }
+        app.journal.clear();
+        app.archive_task();
+        assert_eq!(logged(&mut app), vec![(EventKind::Archived, None)]);
+        app.enter_archive();
+        app.restore_archived();
+        assert_eq!(
+            logged(&mut app),
+            vec![entry(EventKind::Restored, "Backlog")]
+        );
+    }
+
+    #[test]
+    fn undo_and_redo_are_logged_as_such() {
+        let mut app = board(&["write tests"]);
+        app.on_move_right();
+        app.journal.clear();
+        app.undo();
+        assert_eq!(
+            logged(&mut app),
+            vec![entry(EventKind::Undone, "Todo -> Backlog")]
+        );
+        app.redo();
+        assert_eq!(
+            logged(&mut app),
+            vec![entry(EventKind::Redone, "Backlog -> Todo")]
+        );
+    }
+
+    #[test]
+    fn parking_a_task_for_a_swap_is_a_context_switch() {
+        let mut app = board(&["first", "second"]);
+        // both go to todo, the first one then into wip
+        app.columns[0].tasks.index = Some(0);
+        app.on_move_right();
+        app.current_column = 0;
+        app.on_move_right();
+        app.current_tasks_mut().index = app
+            .current_tasks()
+            .items
+            .iter()
+            .position(|task| task.title == "first");
+        app.on_move_right();
+        app.current_column = 1;
+        app.journal.clear();
+        app.swap_into_wip();
+        let events = std::mem::take(&mut app.journal);
+        let kinds: Vec<(&str, EventKind)> = events
+            .iter()
+            .map(|event| (event.title.as_str(), event.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![("first", EventKind::Paused), ("second", EventKind::Started)]
+        );
+        let since = Local::now() - chrono::Duration::hours(1);
+        assert_eq!(app.build_report(&events, since).context_switches, 1);
     }
 }
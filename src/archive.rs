@@ -0,0 +1,37 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+
+/// When finished tasks leave the last column for the archive on their own,
+/// written as `archive_after: 7` for days or `archive_after: week`.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+#[serde(untagged)]
+pub enum Rollover {
+    Days(u32),
+    Every(Period),
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    // a new week starts on monday
+    Week,
+}
+
+impl Rollover {
+    /// Whether a task finished at `finished_at` belongs in the archive by `now`.
+    pub fn is_due(&self, finished_at: DateTime<Local>, now: DateTime<Local>) -> bool {
+        match self {
+            Rollover::Days(days) => now - finished_at >= Duration::days(*days as i64),
+            Rollover::Every(Period::Week) => finished_at < start_of_week(now),
+        }
+    }
+}
+
+fn start_of_week(now: DateTime<Local>) -> DateTime<Local> {
+    let today = now.date_naive();
+    let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    Local
+        .from_local_datetime(&monday.and_time(NaiveTime::MIN))
+        .earliest()
+        .unwrap_or(now)
+}
@@ -0,0 +1,383 @@
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+
+use crate::{
+    app::{App, Boards},
+    journal,
+    messages::Messages,
+    report::{days_back, parse_since, Report},
+    storage::{get_initial_board, journal_path, lock_board, save_board, Lock},
+    task::{format_duration, parse_input, Task},
+};
+
+const USAGE: &str = "usage: ow [options] [command]
+
+options:
+  -f, --file <path>   use the board file at <path> (also read from $ONE_WIP_FILE)
+  -g, --global        use the global board in $XDG_DATA_HOME/one-wip
+  -b, --board <name>  use the board called <name> in the file, add starts it
+                      if there is none yet
+
+commands:
+  (none)          open the board
+  init            start a board in the current directory, or at the file
+                  given with -f, -g or $ONE_WIP_FILE
+  add <text>      add a task to the first column
+  start [n]       move task n (default: the next one left of wip) into wip
+  done            move the wip task to the last column
+  ls              list the board, numbering every task
+  boards          list the boards in the file
+  rm <n>          remove task n
+  archive [n]     list the archive, or move finished task n into it
+  restore <n>     move archived task n back to the first column
+  report [--since <day>]
+                  sum up the work since a date, today, yesterday, 3d or 2w
+                  (default: the last 7 days)";
+
+pub struct Args {
+    pub file: Option<PathBuf>,
+    pub global: bool,
+    pub board: Option<String>,
+    pub command: Command,
+}
+
+impl Args {
+    pub fn parse(args: &[String]) -> Result<Args, String> {
+        let mut file = None;
+        let mut global = false;
+        let mut board = None;
+        let mut rest = args;
+        // options go before the command, anything after it belongs to the command
+        while let Some((arg, tail)) = rest.split_first() {
+            match arg.as_str() {
+                "-f" | "--file" => {
+                    let (path, tail) = tail
+                        .split_first()
+                        .ok_or_else(|| format!("missing path for {}\n\n{}", arg, USAGE))?;
+                    file = Some(PathBuf::from(path));
+                    rest = tail;
+                }
+                "-b" | "--board" => {
+                    let (name, tail) = tail
+                        .split_first()
+                        .ok_or_else(|| format!("missing name for {}\n\n{}", arg, USAGE))?;
+                    board = Some(name.clone());
+                    rest = tail;
+                }
+                "-g" | "--global" => {
+                    global = true;
+                    rest = tail;
+                }
+                _ => break,
+            }
+        }
+        Ok(Args {
+            file,
+            global,
+            board,
+            command: Command::parse(rest)?,
+        })
+    }
+}
+
+pub enum Command {
+    Tui,
+    Init,
+    Add(String),
+    Start(Option<usize>),
+    Done,
+    Ls,
+    Boards,
+    Rm(usize),
+    Archive(Option<usize>),
+    Restore(usize),
+    Report(Option<String>),
+}
+
+impl Command {
+    pub fn parse(args: &[String]) -> Result<Command, String> {
+        let Some((name, rest)) = args.split_first() else {
+            return Ok(Command::Tui);
+        };
+        match name.as_str() {
+            "add" => {
+                let text = rest.join(" ");
+                if text.trim().is_empty() {
+                    return Err(format!("missing task text\n\n{}", USAGE));
+                }
+                Ok(Command::Add(text))
+            }
+            "start" => match rest {
+                [] => Ok(Command::Start(None)),
+                [n] => Ok(Command::Start(Some(parse_position(n)?))),
+                _ => Err(USAGE.to_string()),
+            },
+            "init" => Ok(Command::Init),
+            "done" => Ok(Command::Done),
+            "ls" => Ok(Command::Ls),
+            "boards" => Ok(Command::Boards),
+            "rm" => match rest {
+                [n] => Ok(Command::Rm(parse_position(n)?)),
+                _ => Err(format!("missing task number\n\n{}", USAGE)),
+            },
+            "archive" => match rest {
+                [] => Ok(Command::Archive(None)),
+                [n] => Ok(Command::Archive(Some(parse_position(n)?))),
+                _ => Err(USAGE.to_string()),
+            },
+            "restore" => match rest {
+                [n] => Ok(Command::Restore(parse_position(n)?)),
+                _ => Err(format!("missing archived task number\n\n{}", USAGE)),
+            },
+            "report" => match rest {
+                [] => Ok(Command::Report(None)),
+                [flag, since] if flag == "--since" => Ok(Command::Report(Some(since.clone()))),
+                _ => Err(USAGE.to_string()),
+            },
+            "help" | "-h" | "--help" => Err(USAGE.to_string()),
+            other => Err(format!("unknown command '{}'\n\n{}", other, USAGE)),
+        }
+    }
+}
+
+// positions are 1-based, as printed by `ow ls`
+fn parse_position(arg: &str) -> Result<usize, String> {
+    match arg.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n - 1),
+        _ => Err(format!("'{}' is not a valid task number", arg)),
+    }
+}
+
+pub fn run(command: Command, file_path: &Path, board: Option<&str>) -> Result<(), Box<dyn Error>> {
+    if let Command::Init = command {
+        return init(file_path);
+    }
+    let edits = matches!(
+        command,
+        Command::Add(_)
+            | Command::Start(_)
+            | Command::Done
+            | Command::Rm(_)
+            | Command::Archive(Some(_))
+            | Command::Restore(_)
+    );
+    // held from reading the board until it is written back, listing a board
+    // that is not there yet leaves no lock file behind
+    let lock = if edits || file_path.is_file() {
+        Some(lock_board(file_path)?)
+    } else {
+        None
+    };
+    if let (Some(Lock::HeldBy(pid)), true) = (&lock, edits) {
+        let holder = match pid {
+            Some(pid) => format!("another ow (pid {})", pid),
+            None => "another ow".to_string(),
+        };
+        return Err(format!("the board is being edited by {}", holder).into());
+    }
+    let mut app = get_initial_board(file_path)?;
+    if let Some(name) = board {
+        match app.find_board(name) {
+            Some(index) => app.switch_board(index),
+            None if matches!(command, Command::Add(_)) => app.open_board(name),
+            None => return Err(format!("there is no board called '{}'", name).into()),
+        }
+    }
+    // finished tasks that are due for the archive go there whatever the command
+    app.roll_over(Local::now());
+    // what the archive took is only news in the tui
+    app.messages = Messages::default();
+    let revision = app.revision;
+    match command {
+        Command::Tui | Command::Init => return Ok(()),
+        Command::Ls => print_board(&app),
+        Command::Boards => print_boards(&app),
+        Command::Archive(None) => print_archive(&app),
+        Command::Report(since) => {
+            let now = Local::now();
+            let since = match since {
+                Some(spec) => parse_since(&spec, now).ok_or_else(|| {
+                    format!(
+                        "could not read the day '{}', try 2024-05-01, yesterday or 7d",
+                        spec
+                    )
+                })?,
+                None => days_back(7, now),
+            };
+            let journal = journal::read(&journal_path(file_path))?;
+            print_report(&app.build_report(&journal, since));
+        }
+        Command::Archive(Some(position)) => {
+            select_task(&mut app, position)?;
+            app.check_archive()?;
+            app.archive_task();
+        }
+        Command::Restore(position) => {
+            if position >= app.archive.items.len() {
+                return Err(format!("there is no archived task {}", position + 1).into());
+            }
+            app.check_limit(0)?;
+            app.archive.index = Some(position);
+            app.restore_archived();
+        }
+        Command::Add(text) => {
+            let input = parse_input(&text, Local::now())?;
+            if input.title.is_empty() {
+                return Err("the task needs a title besides its tags and due date".into());
+            }
+            app.check_limit(0)?;
+            app.check_blocked_by(app.next_id, &input.blocked_by)?;
+            app.input = text;
+            app.add_task();
+        }
+        Command::Start(position) => {
+            let wip = app.wip_column().ok_or("the board has no wip column")?;
+            app.check_limit(wip)?;
+            match position {
+                Some(position) => {
+                    select_task(&mut app, position)?;
+                    if app.current_column >= wip {
+                        return Err(format!(
+                            "task {} is not left of {}, only those can be started",
+                            position + 1,
+                            app.columns[wip].name
+                        )
+                        .into());
+                    }
+                }
+                None => select_next(&mut app, wip)?,
+            }
+            app.check_blocked(wip)?;
+            app.move_to_wip();
+        }
+        Command::Done => {
+            if app.wip_task().is_none() {
+                return Err("there is no task in wip".into());
+            }
+            app.check_limit(app.last_column())?;
+            app.move_to_done();
+        }
+        Command::Rm(position) => {
+            select_task(&mut app, position)?;
+            app.on_remove_task();
+        }
+    }
+    // the app says why an action left the board as it was
+    if app.revision == revision {
+        if let Some(reason) = app.messages.current() {
+            return Err(reason.into());
+        }
+    }
+    // listing the board only writes it back when the archive took something,
+    // and only if no one else is editing it
+    if app.revision > 0 && matches!(lock, Some(Lock::Acquired { .. })) {
+        if !app.journal.is_empty() {
+            journal::append(&journal_path(file_path), &app.journal)?;
+        }
+        save_board(file_path, &Boards::from(app))?;
+    }
+    Ok(())
+}
+
+fn init(path: &Path) -> Result<(), Box<dyn Error>> {
+    if path.exists() {
+        return Err(format!("{} already exists", path.display()).into());
+    }
+    save_board(path, &Boards::from(App::new()))?;
+    println!("Created {}", path.display());
+    Ok(())
+}
+
+// tasks are numbered across the whole board, column after column
+fn select_task(app: &mut App, position: usize) -> Result<(), String> {
+    let mut skipped = 0;
+    for (column, tasks) in app.columns.iter_mut().map(|c| &mut c.tasks).enumerate() {
+        if position < skipped + tasks.items.len() {
+            tasks.index = Some(position - skipped);
+            app.current_column = column;
+            return Ok(());
+        }
+        skipped += tasks.items.len();
+    }
+    Err(format!("there is no task {}", position + 1))
+}
+
+fn select_next(app: &mut App, wip: usize) -> Result<(), String> {
+    let column = wip
+        .checked_sub(1)
+        .filter(|&column| !app.columns[column].tasks.items.is_empty())
+        .ok_or("there is no task waiting to be started")?;
+    app.columns[column].tasks.index = if app.pull_top_priority {
+        app.top_priority(column)
+    } else {
+        Some(0)
+    };
+    app.current_column = column;
+    Ok(())
+}
+
+fn print_board(app: &App) {
+    let mut number = 0;
+    for column in app.columns.iter() {
+        println!("{}", column.name);
+        for task in column.tasks.items.iter() {
+            number += 1;
+            println!("  {}. {}", number, describe(task));
+        }
+    }
+}
+
+fn print_report(report: &Report) {
+    println!("Since {}", report.since.format("%Y-%m-%d"));
+    for (name, value) in report.rows() {
+        println!("  {:<18}{}", name, value);
+    }
+    // a bar of # for every task finished that day
+    println!("Finished by day");
+    for day in report.per_day.iter() {
+        println!(
+            "  {}  {:<10} {}",
+            day.date.format("%a %Y-%m-%d"),
+            "#".repeat(day.finished as usize),
+            day.finished
+        );
+    }
+}
+
+fn print_archive(app: &App) {
+    for (index, task) in app.archive.items.iter().enumerate() {
+        println!("  {}. {}", index + 1, describe(task));
+    }
+}
+
+fn print_boards(app: &App) {
+    for (index, board) in app.boards.iter().enumerate() {
+        match app.board_wip_task(index) {
+            Some(task) => println!("{}: {}", board.name(), describe(task)),
+            None => println!("{}", board.name()),
+        }
+    }
+}
+
+fn describe(task: &Task) -> String {
+    let mut description = task.tagged_title();
+    if let Some(priority) = task.priority {
+        description = format!("P{} {}", priority, description);
+    }
+    if let Some((done, total)) = task.progress() {
+        description.push_str(&format!(" {}/{}", done, total));
+    }
+    let now = Local::now();
+    if task.is_tracked() {
+        let elapsed = format_duration(task.elapsed(now));
+        description.push_str(&format!(" [{}]", elapsed));
+    }
+    if let Some(label) = task.due_label(now) {
+        description.push_str(&format!(" ({})", label));
+    }
+    description
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+use crate::app::Board;
+
+// oldest snapshots are dropped past this many undo steps
+const LIMIT: usize = 100;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct History {
+    #[serde(default)]
+    undo: Vec<Board>,
+    #[serde(default)]
+    redo: Vec<Board>,
+}
+
+impl History {
+    /// Remembers the board as it was before a change. Any redo steps are
+    /// dropped since they no longer follow from the current board.
+    pub fn record(&mut self, before: Board) {
+        self.undo.push(before);
+        if self.undo.len() > LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    pub fn undo(&mut self, current: Board) -> Option<Board> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    pub fn redo(&mut self, current: Board) -> Option<Board> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        Some(next)
+    }
+}
@@ -0,0 +1,99 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::Path,
+};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+/// Something that happened to a task, one line of the journal.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Event {
+    pub at: DateTime<Local>,
+    // as saved in the board file, see Board::name
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub board: String,
+    pub task: u64,
+    pub title: String,
+    pub kind: EventKind,
+    // the columns a task moved between, or which of its fields were edited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    // the positions of the columns a task moved between, the first one being 0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<usize>,
+}
+
+impl Event {
+    /// Whether a task was taken out of wip back to an earlier column before it
+    /// was done. Moving on to a column like review is not a switch.
+    pub fn is_context_switch(&self) -> bool {
+        self.kind == EventKind::Paused
+            && matches!((self.from, self.to), (Some(from), Some(to)) if to < from)
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Created,
+    Edited,
+    Started,
+    Paused,
+    Finished,
+    Moved,
+    Reordered,
+    Deleted,
+    Archived,
+    Restored,
+    Undone,
+    Redone,
+}
+
+impl EventKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventKind::Created => "created",
+            EventKind::Edited => "edited",
+            EventKind::Started => "started",
+            EventKind::Paused => "paused",
+            EventKind::Finished => "finished",
+            EventKind::Moved => "moved",
+            EventKind::Reordered => "reordered",
+            EventKind::Deleted => "deleted",
+            EventKind::Archived => "archived",
+            EventKind::Restored => "restored",
+            EventKind::Undone => "undone",
+            EventKind::Redone => "redone",
+        }
+    }
+}
+
+/// Adds the events to the end of the journal, one JSON object per line.
+/// The journal is never rewritten, only appended to.
+pub fn append(path: &Path, events: &[Event]) -> io::Result<()> {
+    let mut lines = String::new();
+    for event in events {
+        lines.push_str(&serde_json::to_string(event)?);
+        lines.push('\n');
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(lines.as_bytes())
+}
+
+/// Everything in the journal, oldest first. A missing journal is an empty
+/// one, and lines that can't be read, say from a newer ow, are skipped.
+pub fn read(path: &Path) -> io::Result<Vec<Event>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
@@ -1,44 +1,80 @@
-use app::{App, Board, Mode};
+use app::{App, Boards, Mode};
+use cli::{Args, Command};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event as CEvent, KeyCode},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event as CEvent, KeyCode, KeyEvent,
+        KeyModifiers,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::{
+    env,
     error::Error,
-    fs::read_to_string,
-    io,
+    io::{self, Write},
     path::Path,
+    process,
     time::{Duration, Instant},
 };
+use storage::{
+    board_to_init, get_initial_board, journal_path, locate_board, lock_board, sync_board, Lock,
+    StoreEvent, StoreRequest,
+};
 use tokio::sync::mpsc;
 use ui::draw;
 
 mod app;
+mod archive;
+mod cli;
+mod history;
+mod journal;
+mod messages;
+mod pomodoro;
+mod report;
+mod search;
+mod storage;
+mod task;
 mod ui;
 
-fn get_initial_board(file_path: &str) -> Result<App, Box<dyn Error>> {
-    // if file_path exists and its a file
-    if Path::new(file_path).exists() && Path::new(file_path).is_file() {
-        let contents = read_to_string(file_path)?;
-        let board: Board = serde_yaml::from_str(contents.as_str())?;
-        return Ok(App::from(board));
-    }
-    Ok(App::new())
-}
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let (tx, mut rx) = mpsc::channel::<Board>(100);
-    let writer_task = tokio::spawn(async move {
-        while let Some(board) = rx.recv().await {
-            let content = serde_yaml::to_string(&board).unwrap();
-            let _ = tokio::fs::write(".one_wip.yml", content).await;
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args = match Args::parse(&args) {
+        Ok(args) => args,
+        Err(usage) => {
+            eprintln!("{}", usage);
+            process::exit(2);
         }
-    });
+    };
+    let file_path = match args.command {
+        Command::Init => board_to_init(args.file, args.global)?,
+        _ => locate_board(args.file, args.global)?,
+    };
+    if !matches!(args.command, Command::Tui) {
+        if let Err(e) = cli::run(args.command, &file_path, args.board.as_deref()) {
+            eprintln!("ow: {}", e);
+            process::exit(1);
+        }
+        return Ok(());
+    }
 
-    let mut app = get_initial_board(".one_wip.yml")?;
+    let (tx, rx) = mpsc::channel::<StoreRequest>(100);
+    let (events_tx, mut events_rx) = mpsc::unbounded_channel::<StoreEvent>();
+    // held until ow exits, a second ow on the same board only gets to look at it
+    let lock = lock_board(&file_path)?;
+    let mut app = get_initial_board(&file_path)?;
+    if let Lock::HeldBy(pid) = &lock {
+        app.enter_read_only(*pid);
+    }
+    if let Some(name) = &args.board {
+        app.open_board(name);
+    }
+    let journal = journal_path(&file_path);
+    let writer_task = tokio::spawn(sync_board(file_path, rx, events_tx));
+    let mut saved_revision = app.revision;
+    // the version of the board on disk the app was last brought up to date with
+    let mut disk_version = 0;
 
     // setup terminal
     enable_raw_mode()?;
@@ -51,6 +87,29 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let ui_task = tokio::spawn(async move {
         let mut last_tick = Instant::now();
         loop {
+            app.on_tick();
+            while let Ok(event) = events_rx.try_recv() {
+                match event {
+                    StoreEvent::Saved => app.on_saved(),
+                    StoreEvent::Failed(message) => app.report(&message),
+                    StoreEvent::Unsaved(reason) => app.on_save_failed(&reason),
+                    // changes the writer has not seen yet would be lost by a reload
+                    StoreEvent::Changed(_, _) if app.revision != saved_revision => {
+                        app.on_conflict()
+                    }
+                    StoreEvent::Changed(board, version) => {
+                        app.reload(*board);
+                        saved_revision = app.revision;
+                        disk_version = version;
+                    }
+                    StoreEvent::Conflict => app.on_conflict(),
+                }
+            }
+            if app.bell {
+                app.bell = false;
+                terminal.backend_mut().write_all(b"\x07").unwrap();
+                terminal.backend_mut().flush().unwrap();
+            }
             terminal.draw(|f| draw(f, &mut app)).unwrap();
             let timeout = tick_rate
                 .checked_sub(last_tick.elapsed())
@@ -58,112 +117,44 @@ async fn main() -> Result<(), Box<dyn Error>> {
             if crossterm::event::poll(timeout).unwrap() {
                 if let CEvent::Key(key) = event::read().unwrap() {
                     if key.kind == event::KeyEventKind::Press {
-                        match app.current_mode {
-                            Mode::Overview => match key.code {
-                                // quit
-                                KeyCode::Char('q') => break,
-                                KeyCode::Esc => break,
-
-                                // move cursor
-                                KeyCode::Char('k') => app.on_up(),
-                                KeyCode::Char('l') => app.on_right(),
-                                KeyCode::Char('j') => app.on_down(),
-                                KeyCode::Char('h') => app.on_left(),
-
-                                // move task
-                                KeyCode::Char('J') => {
-                                    app.on_move_down();
-                                    tx.send(Board::from(app.clone())).await.unwrap();
-                                }
-                                KeyCode::Char('K') => {
-                                    app.on_move_up();
-                                    tx.send(Board::from(app.clone())).await.unwrap();
-                                }
-                                KeyCode::Char('L') => {
-                                    app.on_move_right();
-                                    tx.send(Board::from(app.clone())).await.unwrap();
-                                }
-                                KeyCode::Char('H') => {
-                                    app.on_move_left();
-                                    tx.send(Board::from(app.clone())).await.unwrap();
-                                }
-
-                                // add task
-                                KeyCode::Char('a') => app.enter_add_mode(),
-                                KeyCode::Char('A') => app.enter_add_mode(),
-
-                                // edit task
-                                KeyCode::Char('e') => app.enter_edit_mode(),
-                                KeyCode::Char('E') => app.enter_edit_mode(),
-
-                                // remove task
-                                KeyCode::Char('d') => {
-                                    app.on_remove_task();
-                                    tx.send(Board::from(app.clone())).await.unwrap();
-                                }
-                                KeyCode::Char('D') => {
-                                    app.on_remove_task();
-                                    tx.send(Board::from(app.clone())).await.unwrap();
-                                }
-                                KeyCode::Backspace => {
-                                    app.on_remove_task();
-                                    tx.send(Board::from(app.clone())).await.unwrap();
-                                }
-                                KeyCode::Delete => {
-                                    app.on_remove_task();
-                                    tx.send(Board::from(app.clone())).await.unwrap();
-                                }
-
-                                // work
-                                KeyCode::Char('f') => app.enter_focus(),
-                                KeyCode::Char('F') => app.enter_focus(),
-                                KeyCode::Char('W') => app.enter_focus(),
-                                KeyCode::Char('w') => app.enter_focus(),
-
-                                // help
-                                KeyCode::Char('?') => app.enter_help(),
-
-                                _ => {}
-                            },
-                            Mode::Add => match key.code {
-                                KeyCode::Enter => {
-                                    app.add_task();
-                                    tx.send(Board::from(app.clone())).await.unwrap();
-                                }
-                                KeyCode::Char(c) => app.on_input(c),
-                                KeyCode::Backspace => app.on_backspace(),
-                                KeyCode::Esc => app.on_cancel_input(),
-                                _ => {}
-                            },
-                            Mode::Focus => match key.code {
-                                KeyCode::Enter => {
-                                    app.move_to_done();
-                                    tx.send(Board::from(app.clone())).await.unwrap();
-                                }
-                                KeyCode::Esc => app.leave_focus(),
-                                KeyCode::Char('q') => app.leave_focus(),
-                                _ => {}
-                            },
-                            Mode::Edit(idx) => match key.code {
-                                KeyCode::Enter => app.edit_task(idx),
-                                KeyCode::Char(c) => app.on_input(c),
-                                KeyCode::Backspace => app.on_backspace(),
-                                KeyCode::Esc => app.leave_focus(),
-                                _ => {}
-                            },
-                            Mode::Help => match key.code {
-                                KeyCode::Char('q') => app.leave_help(),
-                                KeyCode::Enter => app.leave_help(),
-                                KeyCode::Esc => app.leave_help(),
-                                _ => {}
-                            },
+                        match on_key(&mut app, key, &journal) {
+                            KeyAction::None => {}
+                            KeyAction::Quit => break,
+                            KeyAction::Send(request) => tx.send(request).await.unwrap(),
                         }
                     }
                 }
             };
             last_tick = Instant::now();
-            tx.send(Board::from(app.clone())).await.unwrap();
+            if !app.journal.is_empty() {
+                let events = std::mem::take(&mut app.journal);
+                tx.send(StoreRequest::Journal(events)).await.unwrap();
+            }
+            if app.revision != saved_revision {
+                saved_revision = app.revision;
+                tx.send(StoreRequest::Save(
+                    Box::new(Boards::from(&app)),
+                    disk_version,
+                ))
+                .await
+                .unwrap();
+            }
+            // event::poll blocks the worker thread, give the writer a chance to run
+            tokio::task::yield_now().await;
+        }
+        if !app.journal.is_empty() {
+            let events = std::mem::take(&mut app.journal);
+            tx.send(StoreRequest::Journal(events)).await.unwrap();
+        }
+        if app.revision != saved_revision {
+            tx.send(StoreRequest::Save(
+                Box::new(Boards::from(&app)),
+                disk_version,
+            ))
+            .await
+            .unwrap();
         }
+        drop(tx);
         disable_raw_mode().unwrap();
         execute!(
             terminal.backend_mut(),
@@ -174,6 +165,232 @@ async fn main() -> Result<(), Box<dyn Error>> {
         terminal.show_cursor().unwrap();
     });
 
-    tokio::try_join!(writer_task, ui_task).unwrap();
+    let (saved, _) = tokio::try_join!(writer_task, ui_task).unwrap();
+    if let Err(reason) = saved {
+        eprintln!("ow: {}, the last changes were lost", reason);
+        process::exit(1);
+    }
     Ok(())
 }
+
+// what the ui loop has to do about a key press beyond what it did to the app
+enum KeyAction {
+    None,
+    Quit,
+    Send(StoreRequest),
+}
+
+fn on_key(app: &mut App, key: KeyEvent, journal: &Path) -> KeyAction {
+    match app.current_mode {
+        Mode::Overview => match key.code {
+            // quit
+            KeyCode::Char('q') => return KeyAction::Quit,
+            KeyCode::Esc => return KeyAction::Quit,
+
+            // move cursor
+            KeyCode::Char('k') => app.on_up(),
+            KeyCode::Char('l') => app.on_right(),
+            KeyCode::Char('j') => app.on_down(),
+            KeyCode::Char('h') => app.on_left(),
+
+            // move task
+            KeyCode::Char('J') => app.on_move_down(),
+            KeyCode::Char('K') => app.on_move_up(),
+            KeyCode::Char('L') => app.on_move_right(),
+            KeyCode::Char('H') => app.on_move_left(),
+
+            // history
+            KeyCode::Char('u') => app.undo(),
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => app.redo(),
+
+            // resolve a conflict with the board on disk
+            KeyCode::Char('R') if app.conflict => {
+                app.resolve_conflict();
+                return KeyAction::Send(StoreRequest::Discard);
+            }
+            KeyCode::Char('O') if app.conflict => {
+                app.resolve_conflict();
+                return KeyAction::Send(StoreRequest::Overwrite);
+            }
+
+            // swap the selected task with the one in wip
+            KeyCode::Char('s') => app.swap_into_wip(),
+
+            // priority
+            KeyCode::Char('+') => app.raise_priority(),
+            KeyCode::Char('=') => app.raise_priority(),
+            KeyCode::Char('-') => app.lower_priority(),
+            KeyCode::Char('S') => app.sort_by_priority(),
+
+            // add task
+            KeyCode::Char('a') => app.enter_add_mode(),
+            KeyCode::Char('A') => app.enter_add_mode(),
+
+            // edit task
+            KeyCode::Char('e') => app.enter_edit_mode(),
+            KeyCode::Char('E') => app.enter_edit_mode(),
+            KeyCode::Char('i') => app.enter_describe_mode(),
+            KeyCode::Char('c') => app.enter_checklist(),
+            KeyCode::Char('B') => app.enter_blockers(),
+            KeyCode::Char('z') => app.archive_task(),
+            KeyCode::Char('Z') => app.enter_archive(),
+            KeyCode::Char('m') => match journal::read(journal) {
+                Ok(events) => app.enter_stats(events),
+                Err(e) => app.report(&format!("Could not read the journal: {}", e)),
+            },
+            KeyCode::Char('g') => match journal::read(journal) {
+                Ok(events) => app.enter_log(events),
+                Err(e) => app.report(&format!("Could not read the journal: {}", e)),
+            },
+
+            // details of the selected task
+            KeyCode::Char('v') => app.toggle_details(),
+
+            // remove task
+            KeyCode::Char('d') => app.on_remove_task(),
+            KeyCode::Char('D') => app.on_remove_task(),
+            KeyCode::Backspace => app.on_remove_task(),
+            KeyCode::Delete => app.on_remove_task(),
+
+            // work
+            KeyCode::Char('f') => app.enter_focus(),
+            KeyCode::Char('F') => app.enter_focus(),
+            KeyCode::Char('W') => app.enter_focus(),
+            KeyCode::Char('w') => app.enter_focus(),
+
+            // search
+            KeyCode::Char('/') => app.enter_search_mode(),
+            KeyCode::Char('n') => app.next_match(),
+            KeyCode::Char('N') => app.previous_match(),
+
+            // filter by tag
+            KeyCode::Char('t') => app.enter_filter_mode(),
+            KeyCode::Char('T') => app.clear_filter(),
+
+            // boards
+            KeyCode::Char('b') => app.enter_board_picker(),
+
+            // help
+            KeyCode::Char('?') => app.enter_help(),
+
+            _ => {}
+        },
+        Mode::Add => match key.code {
+            KeyCode::Enter => app.add_task(),
+            KeyCode::Char(c) => app.on_input(c),
+            KeyCode::Backspace => app.on_backspace(),
+            KeyCode::Esc => app.on_cancel_input(),
+            _ => {}
+        },
+        Mode::Focus => match key.code {
+            KeyCode::Enter => app.move_to_done(),
+            KeyCode::Esc => app.leave_focus(),
+            KeyCode::Char('q') => app.leave_focus(),
+            KeyCode::Char('p') => app.toggle_pomodoro(),
+            KeyCode::Char('r') => app.reset_pomodoro(),
+            KeyCode::Char('x') => app.check_next_step(),
+            _ => {}
+        },
+        Mode::Edit(id) => match key.code {
+            KeyCode::Enter => app.edit_task(id),
+            KeyCode::Char(c) => app.on_input(c),
+            KeyCode::Backspace => app.on_backspace(),
+            KeyCode::Esc => app.leave_focus(),
+            _ => {}
+        },
+        Mode::Search => match key.code {
+            KeyCode::Enter => app.on_search(),
+            KeyCode::Char(c) => app.on_input(c),
+            KeyCode::Backspace => app.on_backspace(),
+            KeyCode::Esc => app.cancel_search(),
+            _ => {}
+        },
+        Mode::Filter => match key.code {
+            KeyCode::Enter => app.apply_filter(),
+            KeyCode::Char(c) => app.on_input(c),
+            KeyCode::Backspace => app.on_backspace(),
+            KeyCode::Esc => app.leave_filter_mode(),
+            _ => {}
+        },
+        Mode::Boards => match key.code {
+            KeyCode::Char('j') | KeyCode::Down => app.pick_next_board(),
+            KeyCode::Char('k') | KeyCode::Up => app.pick_previous_board(),
+            KeyCode::Enter => app.pick_board(),
+            KeyCode::Char('a') => app.enter_add_board_mode(),
+            KeyCode::Esc => app.leave_board_picker(),
+            KeyCode::Char('q') => app.leave_board_picker(),
+            _ => {}
+        },
+        Mode::AddBoard => match key.code {
+            KeyCode::Enter => app.add_board_from_input(),
+            KeyCode::Char(c) => app.on_input(c),
+            KeyCode::Backspace => app.on_backspace(),
+            KeyCode::Esc => app.leave_add_board_mode(),
+            _ => {}
+        },
+        Mode::Describe(id) => match key.code {
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.describe_task(id)
+            }
+            KeyCode::Char(c) => app.on_input(c),
+            KeyCode::Enter => app.on_newline(),
+            KeyCode::Backspace => app.on_backspace(),
+            KeyCode::Esc => app.leave_focus(),
+            _ => {}
+        },
+        Mode::Checklist(id) => match key.code {
+            KeyCode::Char('j') | KeyCode::Down => app.checklist_down(id),
+            KeyCode::Char('k') | KeyCode::Up => app.checklist_up(),
+            KeyCode::Char(' ') | KeyCode::Char('x') => app.toggle_step(id),
+            KeyCode::Enter => app.toggle_step(id),
+            KeyCode::Char('a') => app.enter_add_step_mode(id),
+            KeyCode::Char('d') => app.remove_step(id),
+            KeyCode::Esc => app.leave_checklist(),
+            KeyCode::Char('q') => app.leave_checklist(),
+            _ => {}
+        },
+        Mode::AddStep(id) => match key.code {
+            KeyCode::Enter => app.add_step(id),
+            KeyCode::Char(c) => app.on_input(c),
+            KeyCode::Backspace => app.on_backspace(),
+            KeyCode::Esc => app.leave_add_step_mode(id),
+            _ => {}
+        },
+        Mode::Blockers(_) => match key.code {
+            KeyCode::Esc => app.leave_blockers(),
+            KeyCode::Char('q') => app.leave_blockers(),
+            _ => {}
+        },
+        Mode::Archive => match key.code {
+            KeyCode::Char('j') | KeyCode::Down => app.archive.down(),
+            KeyCode::Char('k') | KeyCode::Up => app.archive.up(),
+            KeyCode::Char('r') | KeyCode::Enter => app.restore_archived(),
+            KeyCode::Char('d') => app.remove_archived(),
+            KeyCode::Esc => app.leave_archive(),
+            KeyCode::Char('q') => app.leave_archive(),
+            _ => {}
+        },
+        Mode::Stats => match key.code {
+            KeyCode::Char('d') => app.stats_days = 1,
+            KeyCode::Char('w') => app.stats_days = 7,
+            KeyCode::Char('m') => app.stats_days = 30,
+            KeyCode::Esc => app.leave_stats(),
+            KeyCode::Char('q') => app.leave_stats(),
+            _ => {}
+        },
+        Mode::Log => match key.code {
+            KeyCode::Char('j') | KeyCode::Down => app.log.down(),
+            KeyCode::Char('k') | KeyCode::Up => app.log.up(),
+            KeyCode::Esc => app.leave_log(),
+            KeyCode::Char('q') => app.leave_log(),
+            _ => {}
+        },
+        Mode::Help => match key.code {
+            KeyCode::Char('q') => app.leave_help(),
+            KeyCode::Enter => app.leave_help(),
+            KeyCode::Esc => app.leave_help(),
+            _ => {}
+        },
+    }
+    KeyAction::None
+}
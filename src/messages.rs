@@ -0,0 +1,51 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+// how long each message stays in the status bar
+const TIMEOUT: Duration = Duration::from_secs(3);
+// a burst of refused key presses should not keep the status bar busy for long
+const LIMIT: usize = 3;
+
+#[derive(Debug, Clone, Default)]
+pub struct Messages {
+    queue: VecDeque<String>,
+    // when the message at the front of the queue was first shown
+    shown_at: Option<Instant>,
+}
+
+impl Messages {
+    /// Queues a message, unless the same one is already the last in line.
+    /// Past the limit the oldest message makes room for the new one.
+    pub fn push(&mut self, message: String) {
+        if self.queue.back() == Some(&message) {
+            return;
+        }
+        if self.queue.len() == LIMIT {
+            self.queue.pop_front();
+            self.shown_at = None;
+        }
+        self.queue.push_back(message);
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.queue.front().map(String::as_str)
+    }
+
+    /// Moves on to the next message once the current one has been up long enough.
+    pub fn tick(&mut self, now: Instant) {
+        match self.shown_at {
+            None if !self.queue.is_empty() => self.shown_at = Some(now),
+            Some(shown_at) if now.duration_since(shown_at) >= TIMEOUT => {
+                self.queue.pop_front();
+                self.shown_at = if self.queue.is_empty() {
+                    None
+                } else {
+                    Some(now)
+                };
+            }
+            _ => {}
+        }
+    }
+}
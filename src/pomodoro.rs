@@ -0,0 +1,137 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(default)]
+pub struct PomodoroConfig {
+    // all lengths are in minutes
+    pub work: u64,
+    pub short_break: u64,
+    pub long_break: u64,
+    // a long break replaces every nth short break
+    pub long_break_every: u32,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> PomodoroConfig {
+        PomodoroConfig {
+            work: 25,
+            short_break: 5,
+            long_break: 15,
+            long_break_every: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl Phase {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Work => "Work",
+            Phase::ShortBreak => "Short break",
+            Phase::LongBreak => "Long break",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Pomodoro {
+    pub config: PomodoroConfig,
+    pub phase: Phase,
+    // work phases finished since the last long break
+    completed: u32,
+    // set while the countdown is running, otherwise `remaining` holds the time left
+    deadline: Option<Instant>,
+    remaining: Duration,
+}
+
+impl Pomodoro {
+    pub fn new(config: PomodoroConfig) -> Pomodoro {
+        let mut pomodoro = Pomodoro {
+            config,
+            phase: Phase::Work,
+            completed: 0,
+            deadline: None,
+            remaining: Duration::ZERO,
+        };
+        pomodoro.remaining = pomodoro.length(Phase::Work);
+        pomodoro
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.deadline.is_some()
+    }
+
+    pub fn toggle(&mut self, now: Instant) {
+        match self.deadline.take() {
+            Some(deadline) => self.remaining = deadline.saturating_duration_since(now),
+            None => self.deadline = now.checked_add(self.remaining),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Pomodoro::new(self.config.clone());
+    }
+
+    /// Advances the countdown and returns the phase that just ended, if any.
+    /// The next phase starts right away so the cycle keeps going by itself.
+    pub fn tick(&mut self, now: Instant) -> Option<Phase> {
+        let deadline = self.deadline?;
+        if now < deadline {
+            return None;
+        }
+        let ended = self.phase;
+        self.phase = match ended {
+            Phase::Work => {
+                self.completed += 1;
+                if self.completed >= self.config.long_break_every.max(1) {
+                    self.completed = 0;
+                    Phase::LongBreak
+                } else {
+                    Phase::ShortBreak
+                }
+            }
+            Phase::ShortBreak | Phase::LongBreak => Phase::Work,
+        };
+        // a phase too long to count down to waits for p like a paused one
+        self.remaining = self.length(self.phase);
+        self.deadline = now.checked_add(self.remaining);
+        Some(ended)
+    }
+
+    pub fn remaining(&self, now: Instant) -> Duration {
+        match self.deadline {
+            Some(deadline) => deadline.saturating_duration_since(now),
+            None => self.remaining,
+        }
+    }
+
+    /// Fraction of the current phase that has already passed.
+    pub fn progress(&self, now: Instant) -> f64 {
+        let length = self.length(self.phase).as_secs_f64();
+        if length == 0.0 {
+            return 1.0;
+        }
+        (1.0 - self.remaining(now).as_secs_f64() / length).clamp(0.0, 1.0)
+    }
+
+    // a phase of no length would end on every tick, so it lasts a minute at least
+    fn length(&self, phase: Phase) -> Duration {
+        let minutes = match phase {
+            Phase::Work => self.config.work,
+            Phase::ShortBreak => self.config.short_break,
+            Phase::LongBreak => self.config.long_break,
+        };
+        minutes
+            .max(1)
+            .checked_mul(60)
+            .map_or(Duration::MAX, Duration::from_secs)
+    }
+}
@@ -0,0 +1,198 @@
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime, TimeZone};
+
+use crate::{journal::Event, task::Task};
+
+/// How the work went since a given day, worked out from the finished tasks
+/// and the journal.
+pub struct Report {
+    pub since: DateTime<Local>,
+    pub finished: usize,
+    pub time_in_wip: Duration,
+    // tasks taken out of wip before they were done
+    pub context_switches: usize,
+    // from when a task was created to when it was done, summed over the finished tasks
+    pub cycle_time: Duration,
+    // one entry for each day from `since` up to today
+    pub per_day: Vec<Day>,
+}
+
+pub struct Day {
+    pub date: NaiveDate,
+    pub finished: u64,
+    // spent in wip by the tasks finished that day
+    pub minutes_in_wip: u64,
+}
+
+impl Report {
+    /// `tasks` are the ones that may have been finished, on the board or in the
+    /// archive, and `journal` is what happened on the same board.
+    pub fn new<'a>(
+        tasks: impl Iterator<Item = &'a Task>,
+        journal: &[Event],
+        since: DateTime<Local>,
+        now: DateTime<Local>,
+    ) -> Report {
+        let finished: Vec<(&Task, DateTime<Local>)> = tasks
+            .filter_map(|task| Some((task, task.finished_at?)))
+            .filter(|&(_, finished_at)| finished_at >= since)
+            .collect();
+        let mut per_day = vec![];
+        let mut day = since.date_naive();
+        while day <= now.date_naive() {
+            let that_day: Vec<&Task> = finished
+                .iter()
+                .filter(|(_, finished_at)| finished_at.date_naive() == day)
+                .map(|&(task, _)| task)
+                .collect();
+            per_day.push(Day {
+                date: day,
+                finished: that_day.len() as u64,
+                minutes_in_wip: that_day
+                    .iter()
+                    .map(|task| task.elapsed(now).num_minutes().max(0) as u64)
+                    .sum(),
+            });
+            day = match day.succ_opt() {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        Report {
+            since,
+            finished: finished.len(),
+            time_in_wip: finished
+                .iter()
+                .map(|(task, _)| task.elapsed(now))
+                .fold(Duration::zero(), |total, spent| total + spent),
+            context_switches: journal
+                .iter()
+                .filter(|event| event.is_context_switch() && event.at >= since)
+                .count(),
+            cycle_time: finished
+                .iter()
+                .map(|&(task, finished_at)| (finished_at - task.created_at).max(Duration::zero()))
+                .fold(Duration::zero(), |total, cycle| total + cycle),
+            per_day,
+        }
+    }
+
+    pub fn average_time_in_wip(&self) -> Option<Duration> {
+        self.average(self.time_in_wip)
+    }
+
+    pub fn average_cycle_time(&self) -> Option<Duration> {
+        self.average(self.cycle_time)
+    }
+
+    fn average(&self, total: Duration) -> Option<Duration> {
+        match self.finished {
+            0 => None,
+            finished => Some(total / finished as i32),
+        }
+    }
+
+    /// The figures as label and value pairs, for printing them as a table.
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        let average = |duration: Option<Duration>| duration.map_or("-".to_string(), format_span);
+        vec![
+            ("Finished", self.finished.to_string()),
+            ("Time in wip", format_span(self.time_in_wip)),
+            ("Average in wip", average(self.average_time_in_wip())),
+            ("Context switches", self.context_switches.to_string()),
+            ("Average cycle", average(self.average_cycle_time())),
+        ]
+    }
+}
+
+/// The start of the day `days` days before today, so 1 is today and 7 the last week.
+pub fn days_back(days: i64, now: DateTime<Local>) -> DateTime<Local> {
+    checked_days_back(days, now).unwrap_or(now)
+}
+
+// none when the day would be further back than a date can go
+fn checked_days_back(days: i64, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let back = Duration::try_days(days.max(1) - 1)?;
+    start_of(now.date_naive().checked_sub_signed(back)?)
+}
+
+/// Reads the day a report starts from: a date, today, yesterday, or a number
+/// of days or weeks back such as 7d or 2w.
+pub fn parse_since(spec: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    match spec.to_lowercase().as_str() {
+        "today" => return Some(days_back(1, now)),
+        "yesterday" => return Some(days_back(2, now)),
+        _ => {}
+    }
+    if let Ok(day) = NaiveDate::parse_from_str(spec, "%Y-%m-%d") {
+        return start_of(day);
+    }
+    let unit = spec.chars().last()?;
+    let amount: i64 = spec[..spec.len() - unit.len_utf8()].parse().ok()?;
+    match unit {
+        'd' => checked_days_back(amount, now),
+        'w' => checked_days_back(amount.checked_mul(7)?, now),
+        _ => None,
+    }
+}
+
+fn start_of(day: NaiveDate) -> Option<DateTime<Local>> {
+    Local
+        .from_local_datetime(&day.and_time(NaiveTime::MIN))
+        .earliest()
+}
+
+// durations in a report run from minutes to weeks, only the two largest units are worth showing
+fn format_span(duration: Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    let (days, hours, minutes) = (minutes / 1440, minutes % 1440 / 60, minutes % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(day: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 5, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn start_days() {
+        let now = Local.with_ymd_and_hms(2024, 5, 15, 10, 0, 0).unwrap();
+        let cases = [
+            ("today", day(15)),
+            ("Yesterday", day(14)),
+            ("0d", day(15)),
+            ("1d", day(15)),
+            ("7d", day(9)),
+            ("2w", day(2)),
+            ("2024-05-01", day(1)),
+        ];
+        for (spec, since) in cases {
+            assert_eq!(parse_since(spec, now), Some(since), "--since {}", spec);
+        }
+    }
+
+    #[test]
+    fn start_days_that_are_not() {
+        let now = Local.with_ymd_and_hms(2024, 5, 15, 10, 0, 0).unwrap();
+        let cases = [
+            "",
+            "soon",
+            "3m",
+            "2024-02-30",
+            "999999999999d",
+            "2000000000000000000w",
+            "9999999999999999999w",
+        ];
+        for spec in cases {
+            assert_eq!(parse_since(spec, now), None, "--since {}", spec);
+        }
+    }
+}
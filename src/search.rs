@@ -0,0 +1,27 @@
+/// Matches `query` against `text` the way fuzzy finders do: every character
+/// of the query has to show up in the text, in the same order, but not
+/// necessarily next to each other. Case and spaces in the query are ignored.
+/// Returns the positions of the matched characters in `text`, in chars.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<Vec<usize>> {
+    let mut wanted = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .peekable();
+    // an empty query would match everything, which is no use for jumping around
+    wanted.peek()?;
+    let mut positions = vec![];
+    for (position, c) in text.chars().enumerate() {
+        let Some(&next) = wanted.peek() else {
+            break;
+        };
+        if c.to_lowercase().eq(next.to_lowercase()) {
+            positions.push(position);
+            wanted.next();
+        }
+    }
+    match wanted.peek() {
+        Some(_) => None,
+        None => Some(positions),
+    }
+}
@@ -0,0 +1,540 @@
+use std::{
+    env,
+    error::Error,
+    ffi::OsString,
+    fs::{self, read_to_string, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, SystemTime},
+};
+
+use fs2::FileExt;
+use tokio::{
+    sync::mpsc::{Receiver, UnboundedSender},
+    time::{interval, sleep_until, Instant},
+};
+
+use crate::{
+    app::{App, Board, Boards},
+    journal::{self, Event},
+};
+
+// how long the board has to stay untouched before it gets written
+const DEBOUNCE: Duration = Duration::from_millis(500);
+// how often the file is checked for changes made by someone else
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+pub const BOARD_FILE: &str = ".one_wip.yml";
+
+/// Picks the board to work on. An explicit path wins, then `$ONE_WIP_FILE`,
+/// then the nearest `.one_wip.yml` in the current directory or any of its
+/// parents. Without a project board the global one is used if it exists,
+/// otherwise a new board is started in the current directory.
+pub fn locate_board(file: Option<PathBuf>, global: bool) -> io::Result<PathBuf> {
+    if let Some(file) = chosen_board(file, global)? {
+        return Ok(file);
+    }
+    let cwd = env::current_dir()?;
+    if let Some(file) = cwd
+        .ancestors()
+        .map(|dir| dir.join(BOARD_FILE))
+        .find(|file| file.is_file())
+    {
+        return Ok(file);
+    }
+    match global_board() {
+        Ok(file) if file.is_file() => Ok(file),
+        _ => Ok(cwd.join(BOARD_FILE)),
+    }
+}
+
+/// Where `ow init` starts a board: the file asked for, or `.one_wip.yml` in
+/// the current directory rather than whatever board is found above it.
+pub fn board_to_init(file: Option<PathBuf>, global: bool) -> io::Result<PathBuf> {
+    match chosen_board(file, global)? {
+        Some(file) => Ok(file),
+        None => Ok(PathBuf::from(BOARD_FILE)),
+    }
+}
+
+// the board picked with --file, --global or $ONE_WIP_FILE, if any
+fn chosen_board(file: Option<PathBuf>, global: bool) -> io::Result<Option<PathBuf>> {
+    if let Some(file) = file {
+        return Ok(Some(file));
+    }
+    if global {
+        return global_board().map(Some);
+    }
+    Ok(env::var_os("ONE_WIP_FILE")
+        .filter(|file| !file.is_empty())
+        .map(PathBuf::from))
+}
+
+fn global_board() -> io::Result<PathBuf> {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+        .ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "could not find a data directory")
+        })?;
+    Ok(data_home.join("one-wip").join("board.yml"))
+}
+
+pub enum Lock {
+    // closing the file releases the lock, keep it around while editing the board
+    Acquired { _file: File },
+    HeldBy(Option<u32>),
+}
+
+/// Takes an advisory lock on `<file>.lock` so only one `ow` at a time edits
+/// the board. The holder writes its pid into the lock file so others can tell
+/// who it is. The lock goes away with the process, even if it crashes.
+pub fn lock_board(file_path: &Path) -> io::Result<Lock> {
+    let lock_path = with_suffix(file_path, ".lock");
+    if let Some(dir) = lock_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&lock_path)?;
+    if file.try_lock_exclusive().is_err() {
+        let pid = read_to_string(&lock_path)
+            .ok()
+            .and_then(|pid| pid.trim().parse().ok());
+        return Ok(Lock::HeldBy(pid));
+    }
+    file.set_len(0)?;
+    write!(file, "{}", process::id())?;
+    file.sync_all()?;
+    Ok(Lock::Acquired { _file: file })
+}
+
+/// The journal of everything that happened on the board, kept next to it.
+pub fn journal_path(file_path: &Path) -> PathBuf {
+    with_suffix(file_path, ".journal")
+}
+
+pub fn get_initial_board(file_path: &Path) -> Result<App, Box<dyn Error>> {
+    // if file_path exists and its a file
+    if file_path.exists() && file_path.is_file() {
+        let contents = read_to_string(file_path)?;
+        return Ok(App::from(parse_boards(&contents)?));
+    }
+    Ok(App::new())
+}
+
+// a file holds either a single board or a list of them under `boards`
+fn parse_boards(content: &str) -> Result<Boards, serde_yaml::Error> {
+    let value: serde_yaml::Value = serde_yaml::from_str(content)?;
+    if value.get("boards").is_some() {
+        return serde_yaml::from_value(value);
+    }
+    Ok(Boards::from(serde_yaml::from_value::<Board>(value)?))
+}
+
+fn to_yaml(boards: &Boards) -> Result<String, serde_yaml::Error> {
+    match boards.single() {
+        Some(board) => serde_yaml::to_string(board),
+        None => serde_yaml::to_string(boards),
+    }
+}
+
+/// Writes the board next to its destination first and renames it into place,
+/// so the file on disk is always either the old or the new board, never half
+/// of one. The previous version is kept around as `<file>.bak`.
+pub fn save_board(file_path: &Path, boards: &Boards) -> Result<(), Box<dyn Error>> {
+    let content = to_yaml(boards)?;
+    save_content(file_path, &content)?;
+    Ok(())
+}
+
+fn save_content(path: &Path, content: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = with_suffix(path, ".tmp");
+    write_synced(&tmp, content.as_bytes())?;
+    if path.is_file() {
+        fs::copy(path, with_suffix(path, ".bak"))?;
+    }
+    fs::rename(&tmp, path)
+}
+
+#[derive(Debug)]
+pub enum StoreRequest {
+    // the board, and the version of the file it was built on, from the last Changed
+    Save(Box<Boards>, u64),
+    // write the pending board even though the file changed under it
+    Overwrite,
+    // drop the pending board and load what is on disk instead
+    Discard,
+    // append to the journal right away, it never conflicts with anyone
+    Journal(Vec<Event>),
+}
+
+#[derive(Debug)]
+pub enum StoreEvent {
+    Saved,
+    Failed(String),
+    // the board could not be written, it stays pending and is tried again
+    Unsaved(String),
+    // someone else changed the file and nothing was pending, so the ui can just take it,
+    // saving later boards with the version that comes along
+    Changed(Box<Boards>, u64),
+    // someone else changed the file while the ui had changes of its own
+    Conflict,
+}
+
+/// Keeps the board file in sync with the ui. A burst of changes is coalesced
+/// into a single write of the latest board, and a board that serializes to
+/// what is already on disk is not written again. The file is also watched for
+/// changes made by anyone else, which are handed to the ui, or reported as a
+/// conflict if they would be clobbered by a pending save. A board that could
+/// not be written is tried again, and returned as an error if it still can't
+/// be once the ui is gone.
+pub async fn sync_board(
+    file_path: PathBuf,
+    mut requests: Receiver<StoreRequest>,
+    events: UnboundedSender<StoreEvent>,
+) -> Result<(), String> {
+    let journal = journal_path(&file_path);
+    let mut file = BoardFile::open(file_path);
+    let mut deadline = Instant::now();
+    let mut watch = interval(WATCH_INTERVAL);
+    loop {
+        let event = tokio::select! {
+            request = requests.recv() => match request {
+                Some(StoreRequest::Save(boards, version)) => {
+                    deadline = Instant::now() + DEBOUNCE;
+                    file.queue(*boards, version)
+                }
+                Some(StoreRequest::Overwrite) => file.flush(true),
+                Some(StoreRequest::Discard) => file.discard(),
+                Some(StoreRequest::Journal(events)) => journal::append(&journal, &events)
+                    .err()
+                    .map(|e| StoreEvent::Failed(format!("Could not write the journal: {}", e))),
+                None => {
+                    // the ui is gone, write what is left unless that would clobber someone
+                    if !file.conflict {
+                        if let Some(StoreEvent::Unsaved(reason)) = file.flush(false) {
+                            return Err(reason);
+                        }
+                    }
+                    break;
+                }
+            },
+            _ = sleep_until(deadline), if file.pending.is_some() && !file.conflict => {
+                file.flush(false)
+            }
+            _ = watch.tick() => file.watch(),
+        };
+        if let Some(StoreEvent::Unsaved(_)) = event {
+            deadline = Instant::now() + WATCH_INTERVAL;
+        }
+        if let Some(event) = event {
+            if events.send(event).is_err() {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+struct BoardFile {
+    path: PathBuf,
+    // the content last read from or written to the file, None if there was no file
+    known: Option<String>,
+    stamp: Option<(SystemTime, u64)>,
+    pending: Option<Boards>,
+    conflict: bool,
+    // bumped whenever the ui is handed the board on disk
+    version: u64,
+    // boards built on an older version than this were made before the ui took
+    // the one on disk, writing them would undo what someone else did
+    stale_before: u64,
+}
+
+impl BoardFile {
+    fn open(path: PathBuf) -> BoardFile {
+        BoardFile {
+            known: read_to_string(&path).ok(),
+            stamp: stamp(&path),
+            path,
+            pending: None,
+            conflict: false,
+            version: 0,
+            stale_before: 0,
+        }
+    }
+
+    fn queue(&mut self, boards: Boards, version: u64) -> Option<StoreEvent> {
+        self.pending = Some(boards);
+        if version < self.stale_before {
+            self.conflict = true;
+            return Some(StoreEvent::Conflict);
+        }
+        None
+    }
+
+    // writes the pending board, which stays pending if it can't be written
+    fn flush(&mut self, force: bool) -> Option<StoreEvent> {
+        let boards = self.pending.take()?;
+        let content = match to_yaml(&boards) {
+            Ok(content) => content,
+            Err(e) => {
+                self.pending = Some(boards);
+                return Some(StoreEvent::Unsaved(format!(
+                    "Could not save the board: {}",
+                    e
+                )));
+            }
+        };
+        let on_disk = read_to_string(&self.path).ok();
+        if !force && on_disk != self.known {
+            self.pending = Some(boards);
+            self.conflict = true;
+            return Some(StoreEvent::Conflict);
+        }
+        // what is on disk was chosen to be overwritten, a retry may do so too
+        self.known = on_disk.clone();
+        self.conflict = false;
+        if force {
+            // the ui kept its own board, whatever it was built on
+            self.stale_before = 0;
+        }
+        if on_disk.as_ref() == Some(&content) {
+            self.known = on_disk;
+            return Some(StoreEvent::Saved);
+        }
+        match save_content(&self.path, &content) {
+            Ok(()) => {
+                self.known = Some(content);
+                self.stamp = stamp(&self.path);
+                Some(StoreEvent::Saved)
+            }
+            Err(e) => {
+                self.pending = Some(boards);
+                Some(StoreEvent::Unsaved(format!(
+                    "Could not save the board: {}",
+                    e
+                )))
+            }
+        }
+    }
+
+    fn discard(&mut self) -> Option<StoreEvent> {
+        self.pending = None;
+        self.conflict = false;
+        self.stamp = stamp(&self.path);
+        let content = read_to_string(&self.path).ok()?;
+        self.load(content)
+    }
+
+    fn watch(&mut self) -> Option<StoreEvent> {
+        let stamp = stamp(&self.path);
+        if stamp == self.stamp {
+            return None;
+        }
+        self.stamp = stamp;
+        let on_disk = read_to_string(&self.path).ok();
+        if on_disk == self.known {
+            return None;
+        }
+        if self.pending.is_some() || self.conflict {
+            self.conflict = true;
+            return Some(StoreEvent::Conflict);
+        }
+        match on_disk {
+            Some(content) => self.load(content),
+            // the file was removed, the next save brings it back
+            None => {
+                self.known = None;
+                None
+            }
+        }
+    }
+
+    fn load(&mut self, content: String) -> Option<StoreEvent> {
+        match parse_boards(&content) {
+            Ok(boards) => {
+                self.known = Some(content);
+                self.version += 1;
+                self.stale_before = self.version;
+                Some(StoreEvent::Changed(Box::new(boards), self.version))
+            }
+            Err(e) => Some(StoreEvent::Failed(format!(
+                "Could not load the board from disk: {}",
+                e
+            ))),
+        }
+    }
+}
+
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn write_synced(path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(content)?;
+    file.sync_all()
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a fresh directory for each test, so they can run side by side
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("one-wip-{}-{}", process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn board_with(titles: &[&str]) -> Boards {
+        let mut app = App::new();
+        for title in titles {
+            app.input = title.to_string();
+            app.add_task();
+        }
+        Boards::from(&app)
+    }
+
+    fn write(path: &Path, boards: &Boards) {
+        fs::write(path, to_yaml(boards).unwrap()).unwrap();
+    }
+
+    fn on_disk(path: &Path) -> Boards {
+        parse_boards(&read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn identical_board_is_not_written() {
+        let path = temp_dir("identical").join(BOARD_FILE);
+        let boards = board_with(&["write tests"]);
+        write(&path, &boards);
+        let mut file = BoardFile::open(path.clone());
+        file.queue(boards, 0);
+        assert!(matches!(file.flush(false), Some(StoreEvent::Saved)));
+        assert!(file.pending.is_none());
+        assert!(!with_suffix(&path, ".bak").exists());
+    }
+
+    #[test]
+    fn changed_board_is_written_with_a_backup() {
+        let path = temp_dir("written").join(BOARD_FILE);
+        write(&path, &board_with(&["write tests"]));
+        let mut file = BoardFile::open(path.clone());
+        let boards = board_with(&["write tests", "run them"]);
+        file.queue(boards.clone(), 0);
+        assert!(matches!(file.flush(false), Some(StoreEvent::Saved)));
+        assert_eq!(on_disk(&path), boards);
+        assert!(with_suffix(&path, ".bak").exists());
+    }
+
+    #[test]
+    fn file_changed_under_a_pending_save_is_a_conflict() {
+        let path = temp_dir("conflict").join(BOARD_FILE);
+        write(&path, &board_with(&["write tests"]));
+        let mut file = BoardFile::open(path.clone());
+        let theirs = board_with(&["theirs"]);
+        write(&path, &theirs);
+        let ours = board_with(&["ours"]);
+        file.queue(ours.clone(), 0);
+        assert!(matches!(file.flush(false), Some(StoreEvent::Conflict)));
+        assert!(file.pending.is_some());
+        assert_eq!(on_disk(&path), theirs);
+        // keeping ours writes it after all
+        assert!(matches!(file.flush(true), Some(StoreEvent::Saved)));
+        assert_eq!(on_disk(&path), ours);
+    }
+
+    #[test]
+    fn failed_write_keeps_the_board_pending() {
+        let dir = temp_dir("failed");
+        // a file where the board's directory should be makes every write fail
+        let blocker = dir.join("boards");
+        fs::write(&blocker, "").unwrap();
+        let path = blocker.join(BOARD_FILE);
+        let mut file = BoardFile::open(path.clone());
+        file.queue(board_with(&["write tests"]), 0);
+        assert!(matches!(file.flush(false), Some(StoreEvent::Unsaved(_))));
+        assert!(file.pending.is_some());
+        fs::remove_file(&blocker).unwrap();
+        assert!(matches!(file.flush(false), Some(StoreEvent::Saved)));
+        assert!(file.pending.is_none());
+        assert!(path.is_file());
+    }
+
+    #[test]
+    fn outside_change_is_handed_over_when_nothing_is_pending() {
+        let path = temp_dir("watch").join(BOARD_FILE);
+        write(&path, &board_with(&["write tests"]));
+        let mut file = BoardFile::open(path.clone());
+        assert!(file.watch().is_none());
+        let theirs = board_with(&["theirs", "and more of theirs"]);
+        write(&path, &theirs);
+        match file.watch() {
+            Some(StoreEvent::Changed(boards, 1)) => assert_eq!(*boards, theirs),
+            other => panic!("expected the board from disk, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn save_sent_before_the_ui_took_a_change_is_a_conflict() {
+        let path = temp_dir("ordering").join(BOARD_FILE);
+        write(&path, &board_with(&["write tests"]));
+        let mut file = BoardFile::open(path.clone());
+        let theirs = board_with(&["theirs", "and more of theirs"]);
+        write(&path, &theirs);
+        assert!(matches!(file.watch(), Some(StoreEvent::Changed(_, 1))));
+        // the ui saved this before it saw the change
+        let stale = file.queue(board_with(&["ours"]), 0);
+        assert!(matches!(stale, Some(StoreEvent::Conflict)));
+        assert!(file.conflict);
+        assert_eq!(on_disk(&path), theirs);
+    }
+
+    #[test]
+    fn save_built_on_the_change_is_written() {
+        let path = temp_dir("rebased").join(BOARD_FILE);
+        write(&path, &board_with(&["write tests"]));
+        let mut file = BoardFile::open(path.clone());
+        write(&path, &board_with(&["theirs", "and more of theirs"]));
+        assert!(matches!(file.watch(), Some(StoreEvent::Changed(_, 1))));
+        let ours = board_with(&["theirs", "and more of theirs", "ours"]);
+        assert!(file.queue(ours.clone(), 1).is_none());
+        assert!(matches!(file.flush(false), Some(StoreEvent::Saved)));
+        assert_eq!(on_disk(&path), ours);
+    }
+
+    #[test]
+    fn discard_drops_the_pending_board_for_the_one_on_disk() {
+        let path = temp_dir("discard").join(BOARD_FILE);
+        write(&path, &board_with(&["write tests"]));
+        let mut file = BoardFile::open(path.clone());
+        let theirs = board_with(&["theirs"]);
+        write(&path, &theirs);
+        file.queue(board_with(&["ours"]), 0);
+        assert!(matches!(file.flush(false), Some(StoreEvent::Conflict)));
+        match file.discard() {
+            Some(StoreEvent::Changed(boards, 1)) => assert_eq!(*boards, theirs),
+            other => panic!("expected the board from disk, got {:?}", other),
+        }
+        assert!(file.pending.is_none());
+        assert!(!file.conflict);
+    }
+}
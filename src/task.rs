@@ -0,0 +1,440 @@
+use std::fmt;
+
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+
+// priorities go from P0, the most urgent, down to this one
+pub const LOWEST_PRIORITY: u8 = 3;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Task {
+    // 0 means the task was loaded from a board that predates ids,
+    // the app hands out a real one when it loads the board
+    #[serde(default)]
+    pub id: u64,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default = "Local::now")]
+    pub created_at: DateTime<Local>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Local>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Local>>,
+    // start of the current stay in wip, if the task is there right now
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wip_since: Option<DateTime<Local>>,
+    // seconds spent in wip, not counting the current stay
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub time_spent: i64,
+    // work phases of the pomodoro timer completed while this was the wip task
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub pomodoros: u32,
+    // times the task was parked to make room for another one in wip
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub interruptions: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<DateTime<Local>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checklist: Vec<Step>,
+    // ids of the tasks that have to be done before this one can be started
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocked_by: Vec<u64>,
+}
+
+/// One item of a task's checklist.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Step {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub done: bool,
+}
+
+impl Task {
+    pub fn new(id: u64, title: String) -> Task {
+        Task {
+            id,
+            title,
+            description: None,
+            created_at: Local::now(),
+            started_at: None,
+            finished_at: None,
+            wip_since: None,
+            time_spent: 0,
+            pomodoros: 0,
+            interruptions: 0,
+            tags: vec![],
+            priority: None,
+            due: None,
+            checklist: vec![],
+            blocked_by: vec![],
+        }
+    }
+
+    pub fn start(&mut self, now: DateTime<Local>) {
+        self.started_at.get_or_insert(now);
+        self.finished_at = None;
+        self.wip_since = Some(now);
+    }
+
+    pub fn pause(&mut self, now: DateTime<Local>) {
+        if let Some(since) = self.wip_since.take() {
+            self.time_spent += (now - since).num_seconds().max(0);
+        }
+    }
+
+    pub fn interrupt(&mut self, now: DateTime<Local>) {
+        self.pause(now);
+        self.interruptions += 1;
+    }
+
+    pub fn finish(&mut self, now: DateTime<Local>) {
+        self.pause(now);
+        self.finished_at = Some(now);
+    }
+
+    pub fn elapsed(&self, now: DateTime<Local>) -> Duration {
+        let running = self
+            .wip_since
+            .map(|since| (now - since).max(Duration::zero()))
+            .unwrap_or_else(Duration::zero);
+        Duration::seconds(self.time_spent) + running
+    }
+
+    pub fn is_tracked(&self) -> bool {
+        self.wip_since.is_some() || self.time_spent > 0
+    }
+
+    // tasks without a priority come after all the ones with one
+    pub fn priority_rank(&self) -> u8 {
+        self.priority.unwrap_or(LOWEST_PRIORITY + 1)
+    }
+
+    /// How many steps of the checklist are done, out of how many.
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        if self.checklist.is_empty() {
+            return None;
+        }
+        let done = self.checklist.iter().filter(|step| step.done).count();
+        Some((done, self.checklist.len()))
+    }
+
+    pub fn next_step(&self) -> Option<usize> {
+        self.checklist.iter().position(|step| !step.done)
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|own| own.eq_ignore_ascii_case(tag))
+    }
+
+    pub fn is_overdue(&self, now: DateTime<Local>) -> bool {
+        self.finished_at.is_none() && self.due.is_some_and(|due| due < now)
+    }
+
+    /// How long until the task is due, or for how long it has been overdue,
+    /// e.g. `due in 2d` or `overdue 3h`. Finished tasks are not due anymore.
+    pub fn due_label(&self, now: DateTime<Local>) -> Option<String> {
+        let due = self.due.filter(|_| self.finished_at.is_none())?;
+        let left = due - now;
+        let amount = left.abs();
+        let amount = if amount.num_days() > 0 {
+            format!("{}d", amount.num_days())
+        } else if amount.num_hours() > 0 {
+            format!("{}h", amount.num_hours())
+        } else {
+            format!("{}m", amount.num_minutes())
+        };
+        Some(if left < Duration::zero() {
+            format!("overdue {}", amount)
+        } else {
+            format!("due in {}", amount)
+        })
+    }
+
+    pub fn tagged_title(&self) -> String {
+        let mut title = self.title.clone();
+        for tag in self.tags.iter() {
+            title.push_str(" #");
+            title.push_str(tag);
+        }
+        title
+    }
+
+    /// The title with the tags and due date written after it, the way they
+    /// are typed in.
+    pub fn to_input(&self) -> String {
+        let mut input = self.tagged_title();
+        if let Some(due) = self.due {
+            let format = if due.time() == end_of_day() {
+                "%Y-%m-%d"
+            } else {
+                "%Y-%m-%dT%H:%M"
+            };
+            input.push_str(&format!(" due:{}", due.format(format)));
+        }
+        if !self.blocked_by.is_empty() {
+            let ids: Vec<String> = self.blocked_by.iter().map(u64::to_string).collect();
+            input.push_str(&format!(" after:{}", ids.join(",")));
+        }
+        input
+    }
+}
+
+/// What was typed for a task, taken apart.
+pub struct TaskInput {
+    pub title: String,
+    pub tags: Vec<String>,
+    pub due: Option<DateTime<Local>>,
+    pub blocked_by: Vec<u64>,
+}
+
+/// Splits the `#tag` words, a `due:` date and the `after:` ids of the tasks
+/// it waits on out of what was typed for a task, the rest is the title. Tags
+/// have to start with a letter, so `fix #12` keeps its `#12`.
+pub fn parse_input(text: &str, now: DateTime<Local>) -> Result<TaskInput, String> {
+    let mut title = vec![];
+    let mut tags: Vec<String> = vec![];
+    let mut due = None;
+    let mut blocked_by = vec![];
+    for word in text.split_whitespace() {
+        if let Some(ids) = word.strip_prefix("after:") {
+            for id in ids.split(',') {
+                let id = id.trim_start_matches('#').parse::<u64>().map_err(|_| {
+                    format!(
+                        "Could not read the task id '{}', try after:3 or after:3,5",
+                        id
+                    )
+                })?;
+                if !blocked_by.contains(&id) {
+                    blocked_by.push(id);
+                }
+            }
+            continue;
+        }
+        if let Some(spec) = word.strip_prefix("due:") {
+            due = Some(parse_due(spec, now).ok_or_else(|| {
+                format!(
+                    "Could not read the due date '{}', try due:fri, due:3d or due:2024-05-31",
+                    spec
+                )
+            })?);
+            continue;
+        }
+        match word.strip_prefix('#') {
+            Some(tag) if tag.starts_with(char::is_alphabetic) => {
+                if !tags.iter().any(|known| known.eq_ignore_ascii_case(tag)) {
+                    tags.push(tag.to_string());
+                }
+            }
+            _ => title.push(word),
+        }
+    }
+    Ok(TaskInput {
+        title: title.join(" "),
+        tags,
+        due,
+        blocked_by,
+    })
+}
+
+// a task due on a day is due by the end of it
+fn end_of_day() -> NaiveTime {
+    NaiveTime::from_hms_opt(23, 59, 59).unwrap()
+}
+
+// understands today, tomorrow, weekdays (the next one, today included),
+// 30m, 4h, 3d and 2w from now, and dates with an optional time
+fn parse_due(spec: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let today = now.date_naive();
+    let day = match spec.to_lowercase().as_str() {
+        "today" => Some(today),
+        "tomorrow" | "tmr" => today.succ_opt(),
+        _ => spec
+            .parse::<Weekday>()
+            .ok()
+            .map(|weekday| {
+                let ahead = weekday.days_since(today.weekday());
+                today + Duration::days(ahead as i64)
+            })
+            .or_else(|| NaiveDate::parse_from_str(spec, "%Y-%m-%d").ok()),
+    };
+    if let Some(day) = day {
+        return Local
+            .from_local_datetime(&day.and_time(end_of_day()))
+            .earliest();
+    }
+    if let Ok(at) = NaiveDateTime::parse_from_str(spec, "%Y-%m-%dT%H:%M") {
+        return Local.from_local_datetime(&at).earliest();
+    }
+    let unit = spec.chars().last()?;
+    let amount: i64 = spec[..spec.len() - unit.len_utf8()].parse().ok()?;
+    // anything too far out to be a date reads as a typo
+    let offset = match unit {
+        'm' => Duration::try_minutes(amount),
+        'h' => Duration::try_hours(amount),
+        'd' => Duration::try_days(amount),
+        'w' => Duration::try_weeks(amount),
+        _ => return None,
+    }?;
+    now.checked_add_signed(offset)
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.num_seconds().max(0);
+    let (hours, minutes, seconds) = (secs / 3600, secs % 3600 / 60, secs % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}", minutes, seconds)
+    }
+}
+
+fn is_zero<T: Default + PartialEq>(n: &T) -> bool {
+    *n == T::default()
+}
+
+impl fmt::Display for Task {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.title)
+    }
+}
+
+// boards written before tasks had any metadata store each one as a bare string
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TaskEntry {
+    Title(String),
+    Task(Box<Task>),
+}
+
+impl From<TaskEntry> for Task {
+    fn from(entry: TaskEntry) -> Task {
+        match entry {
+            TaskEntry::Title(title) => Task::new(0, title),
+            TaskEntry::Task(task) => *task,
+        }
+    }
+}
+
+pub fn deserialize_tasks<'de, D>(deserializer: D) -> Result<Vec<Task>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let entries = Vec::<TaskEntry>::deserialize(deserializer)?;
+    Ok(entries.into_iter().map(Task::from).collect())
+}
+
+pub fn deserialize_optional_task<'de, D>(deserializer: D) -> Result<Option<Task>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let entry = Option::<TaskEntry>::deserialize(deserializer)?;
+    Ok(entry.map(Task::from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: u32, hour: u32, minute: u32, second: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2024, 5, day, hour, minute, second)
+            .unwrap()
+    }
+
+    // a Wednesday morning
+    fn now() -> DateTime<Local> {
+        at(15, 10, 0, 0)
+    }
+
+    #[test]
+    fn due_dates() {
+        let cases = [
+            ("today", at(15, 23, 59, 59)),
+            ("tomorrow", at(16, 23, 59, 59)),
+            ("tmr", at(16, 23, 59, 59)),
+            ("wed", at(15, 23, 59, 59)),
+            ("fri", at(17, 23, 59, 59)),
+            ("Mon", at(20, 23, 59, 59)),
+            ("30m", at(15, 10, 30, 0)),
+            ("4h", at(15, 14, 0, 0)),
+            ("3d", at(18, 10, 0, 0)),
+            ("2w", at(29, 10, 0, 0)),
+            ("2024-05-31", at(31, 23, 59, 59)),
+            ("2024-05-31T15:00", at(31, 15, 0, 0)),
+        ];
+        for (spec, due) in cases {
+            assert_eq!(parse_due(spec, now()), Some(due), "due:{}", spec);
+        }
+    }
+
+    #[test]
+    fn due_dates_that_are_not() {
+        let cases = [
+            "",
+            "soon",
+            "3y",
+            "d",
+            "2024-13-01",
+            "99999999d",
+            "99999999999999m",
+            "9999999999999999w",
+            "-99999999999999h",
+        ];
+        for spec in cases {
+            assert_eq!(parse_due(spec, now()), None, "due:{}", spec);
+        }
+    }
+
+    #[test]
+    fn input_is_split_into_title_tags_due_and_blockers() {
+        let input = parse_input("fix login #bug due:tomorrow #UI after:3,#5", now()).unwrap();
+        assert_eq!(input.title, "fix login");
+        assert_eq!(input.tags, vec!["bug", "UI"]);
+        assert_eq!(input.due, Some(at(16, 23, 59, 59)));
+        assert_eq!(input.blocked_by, vec![3, 5]);
+    }
+
+    #[test]
+    fn input_keeps_what_is_not_a_tag_in_the_title() {
+        let cases = [
+            ("fix #12 crash", "fix #12 crash"),
+            ("tag # alone", "tag # alone"),
+            ("  spaced   out  ", "spaced out"),
+        ];
+        for (text, title) in cases {
+            let input = parse_input(text, now()).unwrap();
+            assert_eq!(input.title, title, "{}", text);
+            assert!(input.tags.is_empty(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn input_drops_repeated_tags_and_blockers() {
+        let input = parse_input("ship it #Bug #bug after:2 after:2,4", now()).unwrap();
+        assert_eq!(input.tags, vec!["Bug"]);
+        assert_eq!(input.blocked_by, vec![2, 4]);
+    }
+
+    #[test]
+    fn input_that_can_not_be_read() {
+        let cases = [
+            ("ship it due:99999999d", "Could not read the due date"),
+            ("ship it due:someday", "Could not read the due date"),
+            ("ship it after:a", "Could not read the task id"),
+            ("ship it after:3,", "Could not read the task id"),
+        ];
+        for (text, reason) in cases {
+            match parse_input(text, now()) {
+                Err(e) => assert!(e.starts_with(reason), "{}: {}", text, e),
+                Ok(_) => panic!("{} should not be read", text),
+            }
+        }
+    }
+}
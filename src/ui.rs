@@ -1,31 +1,90 @@
+use std::time::Instant;
+
+use chrono::Local;
 use ratatui::{
     backend::Backend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    text::{Line, Span},
+    widgets::{BarChart, Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph, Wrap},
     Frame,
 };
 
-use crate::app::{App, Column, Mode};
+use crate::{
+    app::{App, Mode},
+    pomodoro::Phase,
+    report::days_back,
+    search::fuzzy_match,
+    task::{format_duration, Step, Task},
+};
+
+const TIMESTAMP: &str = "%Y-%m-%d %H:%M";
 
 pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let screen = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+        .split(f.size());
+    let body = screen[0];
+    let board_modes = matches!(
+        app.current_mode,
+        Mode::Overview | Mode::Search | Mode::Filter | Mode::Boards
+    );
+    let (board, details) = if app.show_details && board_modes {
+        let split = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(65), Constraint::Percentage(35)].as_ref())
+            .split(body);
+        (split[0], Some(split[1]))
+    } else {
+        (body, None)
+    };
+    // the wip column gets a little more room than the others
+    let weights: Vec<u32> = app
+        .columns
+        .iter()
+        .map(|column| if column.wip { 4 } else { 3 })
+        .collect();
+    let total = weights.iter().sum();
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .margin(1)
         .constraints(
-            [
-                Constraint::Percentage(30),
-                Constraint::Percentage(40),
-                Constraint::Percentage(30),
-            ]
-            .as_ref(),
+            weights
+                .iter()
+                .map(|&weight| Constraint::Ratio(weight, total))
+                .collect::<Vec<_>>(),
         )
-        .split(f.size());
+        .split(board);
+    if let Some(area) = details {
+        render_details(f, app, area);
+    }
     match app.current_mode {
-        Mode::Overview => render_overview(app, &chunks, f),
-        Mode::Add => render_input(f, app),
-        Mode::Edit(_) => render_input(f, app),
-        Mode::Focus => render_focus(f, app),
+        Mode::Overview | Mode::Search | Mode::Filter => render_overview(app, &chunks, f),
+        Mode::Add => render_input(f, app, body, "Add Task"),
+        Mode::Edit(_) => render_input(f, app, body, "Add Task"),
+        Mode::AddBoard => render_input(f, app, body, "Add Board"),
+        Mode::Describe(_) => render_editor(f, app, body),
+        Mode::Boards => {
+            render_overview(app, &chunks, f);
+            render_boards(f, app, body);
+        }
+        Mode::Checklist(id) => {
+            render_overview(app, &chunks, f);
+            render_checklist(f, app, body, id);
+        }
+        Mode::AddStep(_) => render_input(f, app, body, "Add Step"),
+        Mode::Log => render_log(f, app, body),
+        Mode::Stats => render_stats(f, app, body),
+        Mode::Archive => {
+            render_overview(app, &chunks, f);
+            render_archive(f, app, body);
+        }
+        Mode::Blockers(id) => {
+            render_overview(app, &chunks, f);
+            render_blockers(f, app, body, id);
+        }
+        Mode::Focus => render_focus(f, app, body),
         Mode::Help => {
             let lines = Vec::from([
                 "h - move left",
@@ -39,8 +98,31 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
                 "q - quit",
                 "a - add task",
                 "e - edit task",
+                "i - edit the task's notes",
+                "c - open the task's checklist",
+                "B - show what the task waits on",
+                "z - archive a finished task",
+                "Z - browse the archive",
+                "g - show the log of the task",
+                "m - stats for the day, week or month",
+                "v - show/hide task details",
                 "d - delete task",
                 "f - focus",
+                "s - swap the task into wip",
+                "b - switch boards",
+                "/ - search tasks",
+                "n/N - next/previous match",
+                "+/- - raise/lower priority",
+                "S - sort the column by priority",
+                "t - show only tasks with a tag",
+                "T - show all tasks",
+                "u - undo",
+                "ctrl-r - redo",
+                "R - reload the board changed on disk",
+                "O - overwrite the board changed on disk",
+                "p - start/pause pomodoro (focus)",
+                "r - reset pomodoro (focus)",
+                "x - check the next step (focus)",
             ])
             .iter()
             .map(|s| ListItem::new(s.to_string()))
@@ -49,11 +131,13 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
                 .block(
                     Block::default()
                         .borders(Borders::ALL)
-                        .style(Style::default().fg(if app.current_column == Column::Done {
-                            Color::Cyan
-                        } else {
-                            Color::White
-                        }))
+                        .style(Style::default().fg(
+                            if app.current_column + 1 == app.columns.len() {
+                                Color::Cyan
+                            } else {
+                                Color::White
+                            },
+                        ))
                         .title("Help"),
                 )
                 .highlight_style(
@@ -62,34 +146,124 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
                         .add_modifier(Modifier::BOLD),
                 )
                 .highlight_symbol("> ");
-            f.render_widget(help, chunks[1]);
+            f.render_widget(help, centered(body));
         }
     }
+    render_status(f, app, screen[1]);
+}
+
+fn render_status(f: &mut Frame<impl Backend>, app: &App, area: Rect) {
+    let prompt = match app.current_mode {
+        Mode::Search => Some("/"),
+        Mode::Filter => Some("Tag: #"),
+        _ => None,
+    };
+    if let Some(prompt) = prompt {
+        f.render_widget(Paragraph::new(format!("{}{}", prompt, app.input)), area);
+        f.set_cursor(area.x + (prompt.len() + app.input.len()) as u16, area.y);
+        return;
+    }
+    let line = if let Some(message) = app.messages.current() {
+        Paragraph::new(message.to_string()).style(Style::default().fg(Color::Yellow))
+    } else if let Some(status) = app.status.as_ref().or(app.read_only.as_ref()) {
+        Paragraph::new(status.clone()).style(Style::default().fg(Color::LightRed))
+    } else {
+        let mut parts = vec![];
+        if app.boards.len() > 1 {
+            parts.push(format!("Board: {}", app.board_name()));
+        }
+        if let Some(tag) = &app.filter {
+            parts.push(format!("Filter: #{} (T to clear)", tag));
+        }
+        if parts.is_empty() {
+            return;
+        }
+        Paragraph::new(parts.join("  ")).style(Style::default().fg(Color::DarkGray))
+    };
+    f.render_widget(line, area);
 }
 
-fn render_focus(f: &mut Frame<impl Backend>, app: &mut App) {
+fn render_focus(f: &mut Frame<impl Backend>, app: &mut App, area: Rect) {
+    let notes_height = app
+        .wip_task()
+        .and_then(|task| task.description.as_ref())
+        .map_or(0, |description| description.lines().count() as u16 + 1);
+    let steps_height = app.wip_task().map_or(0, |task| task.checklist.len() as u16);
     let focus_layout = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
         .constraints(
             [
                 Constraint::Percentage(50),
-                Constraint::Min(3),
+                // title and timer, the checklist and the notes if there are any, inside the borders
+                Constraint::Min(4 + steps_height + notes_height),
+                Constraint::Length(3),
                 Constraint::Percentage(50),
             ]
             .as_ref(),
         )
-        .split(f.size());
-    if let Some(item) = &app.wip {
-        let wip = Paragraph::new(item.to_string())
+        .split(area);
+    if let Some(item) = app.wip_task() {
+        let now = Local::now();
+        let mut timer = format_duration(item.elapsed(now));
+        if item.interruptions > 0 {
+            timer.push_str(&format!(", interrupted {}x", item.interruptions));
+        }
+        let mut timer = vec![Span::raw(timer)];
+        if let Some(label) = item.due_label(now) {
+            timer.push(Span::styled(format!(", {}", label), due_style(item)));
+        }
+        let mut lines = vec![Line::from(item.title.clone()), Line::from(timer)];
+        let next = item.next_step();
+        for (index, step) in item.checklist.iter().enumerate() {
+            let style = if step.done {
+                Style::default().fg(Color::DarkGray)
+            } else if Some(index) == next {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            lines.push(Line::from(Span::styled(step_label(step), style)));
+        }
+        if let Some(description) = &item.description {
+            lines.push(Line::from(""));
+            lines.extend(description.lines().map(|line| Line::from(line.to_string())));
+        }
+        let wip = Paragraph::new(lines)
             .alignment(Alignment::Center)
             .style(Style::default().fg(Color::LightCyan))
             .block(Block::default().borders(Borders::ALL).title("Wip"));
         f.render_widget(wip, focus_layout[1])
     }
+    render_pomodoro(f, app, focus_layout[2]);
+}
+
+fn render_pomodoro(f: &mut Frame<impl Backend>, app: &App, area: Rect) {
+    let now = Instant::now();
+    let pomodoro = &app.pomodoro;
+    let secs = pomodoro.remaining(now).as_secs();
+    let mut label = format!(
+        "{} {:02}:{:02}",
+        pomodoro.phase.name(),
+        secs / 60,
+        secs % 60
+    );
+    if !pomodoro.is_running() {
+        label.push_str(" (p to start)");
+    }
+    let color = match pomodoro.phase {
+        Phase::Work => Color::LightRed,
+        Phase::ShortBreak | Phase::LongBreak => Color::LightGreen,
+    };
+    let gauge = Gauge::default()
+        .block(Block::default().borders(Borders::ALL).title("Pomodoro"))
+        .gauge_style(Style::default().fg(color))
+        .ratio(pomodoro.progress(now))
+        .label(label);
+    f.render_widget(gauge, area);
 }
 
-fn render_input(f: &mut Frame<impl Backend>, app: &mut App) {
+fn render_input(f: &mut Frame<impl Backend>, app: &mut App, area: Rect, title: &str) {
     let input_layout = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
@@ -101,14 +275,14 @@ fn render_input(f: &mut Frame<impl Backend>, app: &mut App) {
             ]
             .as_ref(),
         )
-        .split(f.size());
+        .split(area);
     let input = Paragraph::new(app.input.clone())
         .style(
             Style::default()
                 .add_modifier(Modifier::RAPID_BLINK)
                 .fg(Color::Yellow),
         )
-        .block(Block::default().borders(Borders::ALL).title("Add Task"));
+        .block(Block::default().borders(Borders::ALL).title(title));
     f.render_widget(input, input_layout[1]);
     f.set_cursor(
         input_layout[1].x + app.input.len() as u16 + 1,
@@ -116,10 +290,451 @@ fn render_input(f: &mut Frame<impl Backend>, app: &mut App) {
     )
 }
 
-fn build_task_list<'a>(list: &Vec<String>, title: &str, is_selected: bool) -> List<'a> {
+fn render_editor(f: &mut Frame<impl Backend>, app: &App, area: Rect) {
+    let editor_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(
+            [
+                Constraint::Percentage(20),
+                Constraint::Percentage(60),
+                Constraint::Percentage(20),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+    let area = editor_layout[1];
+    let editor = Paragraph::new(app.input.clone())
+        .style(Style::default().fg(Color::Yellow))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Notes (ctrl-s to save, esc to cancel)"),
+        );
+    f.render_widget(editor, area);
+    // the cursor sits at the end of the text, after a trailing newline it starts the next line
+    let row = app.input.split('\n').count() as u16 - 1;
+    let column = app.input.rsplit('\n').next().unwrap_or_default().len() as u16;
+    f.set_cursor(area.x + column + 1, area.y + row + 1);
+}
+
+fn render_details(f: &mut Frame<impl Backend>, app: &App, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title("Details");
+    let Some(task) = app.current_tasks().selected() else {
+        f.render_widget(Paragraph::new("No task selected").block(block), area);
+        return;
+    };
+    let label = Style::default().fg(Color::DarkGray);
+    let mut lines = vec![Line::from(Span::styled(
+        task.title.clone(),
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+    match &task.description {
+        Some(description) => {
+            lines.extend(description.lines().map(|line| Line::from(line.to_string())))
+        }
+        None => lines.push(Line::from(Span::styled("No notes, i to add some", label))),
+    }
+    lines.push(Line::from(""));
+    let now = Local::now();
+    let mut fields = vec![("Id", task.id.to_string())];
+    if let Some(priority) = task.priority {
+        fields.push(("Priority", format!("P{}", priority)));
+    }
+    if !task.tags.is_empty() {
+        let tags: Vec<String> = task.tags.iter().map(|tag| format!("#{}", tag)).collect();
+        fields.push(("Tags", tags.join(" ")));
+    }
+    if let Some(due) = task.due {
+        let mut value = due.format(TIMESTAMP).to_string();
+        if let Some(label) = task.due_label(now) {
+            value.push_str(&format!(" ({})", label));
+        }
+        fields.push(("Due", value));
+    }
+    fields.push(("Created", task.created_at.format(TIMESTAMP).to_string()));
+    if let Some(started_at) = task.started_at {
+        fields.push(("Started", started_at.format(TIMESTAMP).to_string()));
+    }
+    if let Some(finished_at) = task.finished_at {
+        fields.push(("Finished", finished_at.format(TIMESTAMP).to_string()));
+    }
+    if task.is_tracked() {
+        fields.push(("Time spent", format_duration(task.elapsed(now))));
+    }
+    if !task.blocked_by.is_empty() {
+        let ids: Vec<String> = task
+            .blocked_by
+            .iter()
+            .map(|id| format!("#{}", id))
+            .collect();
+        fields.push(("Waits on", ids.join(" ")));
+    }
+    if let Some((done, total)) = task.progress() {
+        fields.push(("Checklist", format!("{}/{}", done, total)));
+    }
+    if task.pomodoros > 0 {
+        fields.push(("Pomodoros", task.pomodoros.to_string()));
+    }
+    if task.interruptions > 0 {
+        fields.push(("Interrupted", format!("{}x", task.interruptions)));
+    }
+    for (name, value) in fields {
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:<12}", name), label),
+            Span::raw(value),
+        ]));
+    }
+    let details = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(block);
+    f.render_widget(details, area);
+}
+
+fn render_boards(f: &mut Frame<impl Backend>, app: &App, area: Rect) {
+    let popup = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage(30),
+                Constraint::Percentage(40),
+                Constraint::Percentage(30),
+            ]
+            .as_ref(),
+        )
+        .split(centered(area))[1];
+    let items = app
+        .boards
+        .iter()
+        .enumerate()
+        .map(|(index, board)| match app.board_wip_task(index) {
+            Some(task) => ListItem::new(format!("{} [{}]", board.name(), task)),
+            None => ListItem::new(board.name().to_string()),
+        })
+        .collect::<Vec<ListItem>>();
+    let boards = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::Cyan))
+                .title("Boards (a to add)"),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    let mut state = ListState::default().with_selected(Some(app.picked_board));
+    f.render_widget(Clear, popup);
+    f.render_stateful_widget(boards, popup, &mut state);
+}
+
+fn render_checklist(f: &mut Frame<impl Backend>, app: &App, area: Rect, id: u64) {
+    // a reload that removes the task also leaves the checklist
+    let Some((_, task)) = app.find_task(id) else {
+        return;
+    };
+    let popup = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage(25),
+                Constraint::Percentage(50),
+                Constraint::Percentage(25),
+            ]
+            .as_ref(),
+        )
+        .split(centered(area))[1];
+    let items = task
+        .checklist
+        .iter()
+        .map(|step| {
+            let item = ListItem::new(step_label(step));
+            if step.done {
+                item.style(Style::default().fg(Color::DarkGray))
+            } else {
+                item
+            }
+        })
+        .collect::<Vec<ListItem>>();
+    let checklist = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::Cyan))
+                .title(format!(
+                    "{} (space to check, a to add, d to delete)",
+                    task.title
+                )),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    let selected = (!task.checklist.is_empty()).then_some(app.step_index);
+    let mut state = ListState::default().with_selected(selected);
+    f.render_widget(Clear, popup);
+    f.render_stateful_widget(checklist, popup, &mut state);
+}
+
+fn render_stats(f: &mut Frame<impl Backend>, app: &App, area: Rect) {
+    let report = app.build_report(&app.stats_journal, days_back(app.stats_days, Local::now()));
+    let rows = report.rows();
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(
+            [
+                Constraint::Length(rows.len() as u16 + 2),
+                Constraint::Percentage(50),
+                Constraint::Percentage(50),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+    let label = Style::default().fg(Color::DarkGray);
+    let lines: Vec<Line> = rows
+        .into_iter()
+        .map(|(name, value)| {
+            Line::from(vec![
+                Span::styled(format!("{:<18}", name), label),
+                Span::raw(value),
+            ])
+        })
+        .collect();
+    let title = match app.stats_days {
+        1 => "Stats: today".to_string(),
+        days => format!("Stats: the last {} days", days),
+    };
+    let table = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::Cyan))
+            .title(format!("{} (d day, w week, m month)", title)),
+    );
+    f.render_widget(table, layout[0]);
+    // the bars share the width between the days, a month still has to fit
+    let days = report.per_day.len().max(1) as u16;
+    let bar_width = (layout[1].width.saturating_sub(2) / days)
+        .saturating_sub(1)
+        .clamp(1, 9);
+    let labels: Vec<String> = report
+        .per_day
+        .iter()
+        .map(|day| {
+            let format = if bar_width >= 6 { "%a %d" } else { "%d" };
+            day.date.format(format).to_string()
+        })
+        .collect();
+    let finished: Vec<(&str, u64)> = labels
+        .iter()
+        .zip(report.per_day.iter())
+        .map(|(label, day)| (label.as_str(), day.finished))
+        .collect();
+    let minutes: Vec<(&str, u64)> = labels
+        .iter()
+        .zip(report.per_day.iter())
+        .map(|(label, day)| (label.as_str(), day.minutes_in_wip))
+        .collect();
+    let charts = [
+        ("Finished", &finished, Color::LightGreen),
+        (
+            "Minutes in wip, by the day the task was finished",
+            &minutes,
+            Color::LightCyan,
+        ),
+    ];
+    for ((title, data, color), chunk) in charts.into_iter().zip(&layout[1..]) {
+        let chart = BarChart::default()
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .data(data)
+            .bar_width(bar_width)
+            .bar_gap(1)
+            .bar_style(Style::default().fg(color))
+            .value_style(Style::default().fg(Color::Black).bg(color));
+        f.render_widget(chart, *chunk);
+    }
+}
+
+fn render_log(f: &mut Frame<impl Backend>, app: &App, area: Rect) {
+    let label = Style::default().fg(Color::DarkGray);
+    let items = app
+        .log
+        .items
+        .iter()
+        .map(|event| {
+            let mut spans = vec![
+                Span::styled(format!("{}  ", event.at.format(TIMESTAMP)), label),
+                Span::raw(format!("{:<10}", event.kind.name())),
+            ];
+            if let Some(detail) = &event.detail {
+                spans.push(Span::raw(detail.clone()));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect::<Vec<ListItem>>();
+    let title = app
+        .log
+        .items
+        .last()
+        .map_or("", |event| event.title.as_str());
+    let log = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::Cyan))
+                .title(format!("Log: {} (esc to close)", title)),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    let mut state = ListState::default().with_selected(app.log.index);
+    f.render_stateful_widget(log, centered(area), &mut state);
+}
+
+fn render_archive(f: &mut Frame<impl Backend>, app: &App, area: Rect) {
+    let popup = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage(20),
+                Constraint::Percentage(60),
+                Constraint::Percentage(20),
+            ]
+            .as_ref(),
+        )
+        .split(centered(area))[1];
+    let label = Style::default().fg(Color::DarkGray);
+    let items = app
+        .archive
+        .items
+        .iter()
+        .map(|task| {
+            let mut spans = vec![Span::raw(task.tagged_title())];
+            if let Some(finished_at) = task.finished_at {
+                spans.push(Span::styled(
+                    format!(" done {}", finished_at.format(TIMESTAMP)),
+                    label,
+                ));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect::<Vec<ListItem>>();
+    let archive = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::Cyan))
+                .title(format!(
+                    "Archive (r to restore to {}, d to delete)",
+                    app.columns[0].name
+                )),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    let mut state = ListState::default().with_selected(app.archive.index);
+    f.render_widget(Clear, popup);
+    f.render_stateful_widget(archive, popup, &mut state);
+}
+
+fn render_blockers(f: &mut Frame<impl Backend>, app: &App, area: Rect, id: u64) {
+    let Some((_, task)) = app.find_task(id) else {
+        return;
+    };
+    let popup = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage(30),
+                Constraint::Percentage(40),
+                Constraint::Percentage(30),
+            ]
+            .as_ref(),
+        )
+        .split(centered(area))[1];
+    let items = task
+        .blocked_by
+        .iter()
+        .map(|&id| match app.find_task(id) {
+            Some((column, blocker)) => {
+                let line = format!("#{} {} ({})", id, blocker.title, app.columns[column].name);
+                if column + 1 == app.columns.len() {
+                    ListItem::new(line).style(Style::default().fg(Color::DarkGray))
+                } else {
+                    ListItem::new(format!("🔒 {}", line))
+                }
+            }
+            None => ListItem::new(format!("#{} (removed)", id))
+                .style(Style::default().fg(Color::DarkGray)),
+        })
+        .collect::<Vec<ListItem>>();
+    let blockers = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::Cyan))
+            .title(format!("{} waits on", task.title)),
+    );
+    f.render_widget(Clear, popup);
+    f.render_widget(blockers, popup);
+}
+
+fn step_label(step: &Step) -> String {
+    format!("[{}] {}", if step.done { 'x' } else { ' ' }, step.text)
+}
+
+fn build_task_list<'a>(app: &App, list: &[&Task], title: &str, is_selected: bool) -> List<'a> {
+    let now = Local::now();
     let mut lines = vec![];
     for item in list.iter() {
-        lines.push(ListItem::new(item.to_string()));
+        let blocked = app.is_blocked(item);
+        let matched = app
+            .search_query()
+            .and_then(|query| fuzzy_match(query, &item.title))
+            .unwrap_or_default();
+        let mut spans = vec![];
+        if blocked {
+            spans.push(Span::raw("🔒 "));
+        }
+        if let Some(priority) = item.priority {
+            spans.push(Span::styled(
+                format!("P{} ", priority),
+                Style::default()
+                    .fg(priority_color(priority))
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        spans.extend(highlight(&item.title, &matched));
+        if let Some((done, total)) = item.progress() {
+            spans.push(Span::styled(
+                format!(" {}/{}", done, total),
+                Style::default().fg(if done == total {
+                    Color::Green
+                } else {
+                    Color::DarkGray
+                }),
+            ));
+        }
+        if item.is_tracked() {
+            spans.push(Span::raw(format!(
+                " [{}]",
+                format_duration(item.elapsed(now))
+            )));
+        }
+        if let Some(label) = item.due_label(now) {
+            spans.push(Span::styled(format!(" ({})", label), due_style(item)));
+        }
+        for tag in item.tags.iter() {
+            spans.push(Span::raw(" "));
+            spans.push(Span::styled(
+                format!(" {} ", tag),
+                Style::default().fg(Color::Black).bg(tag_color(tag)),
+            ));
+        }
+        let mut line = ListItem::new(Line::from(spans));
+        if blocked {
+            line = line.style(
+                Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::DIM),
+            );
+        } else if item.is_overdue(now) {
+            line = line.style(Style::default().fg(Color::Red));
+        }
+        lines.push(line);
     }
     List::new(lines)
         .block(
@@ -136,22 +751,103 @@ fn build_task_list<'a>(list: &Vec<String>, title: &str, is_selected: bool) -> Li
         .highlight_symbol(if is_selected {"> "} else {"  "})
 }
 
-fn render_overview(app: &mut App, chunks: &[Rect], f: &mut Frame<impl Backend>) {
-    let todo_list = build_task_list(&app.todo.items, "Todo", app.current_column == Column::Todo);
-    let mut todo_list_state = ListState::default().with_selected(app.todo.index);
+fn due_style(task: &Task) -> Style {
+    if task.is_overdue(Local::now()) {
+        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::DarkGray)
+    }
+}
 
-    let wip_tasks: Vec<String> = match &app.wip {
-        Some(task) => vec![task.to_string()],
-        None => vec![],
-    };
+fn priority_color(priority: u8) -> Color {
+    match priority {
+        0 => Color::Red,
+        1 => Color::LightRed,
+        2 => Color::Yellow,
+        _ => Color::Blue,
+    }
+}
 
-    let wip_list = build_task_list(&wip_tasks, "Wip", app.current_column == Column::Wip);
-    let mut wip_list_state = ListState::default().with_selected(app.wip.as_ref().map(|_| 0));
+// the same tag always gets the same color
+fn tag_color(tag: &str) -> Color {
+    const COLORS: [Color; 6] = [
+        Color::LightBlue,
+        Color::LightGreen,
+        Color::LightMagenta,
+        Color::LightYellow,
+        Color::LightCyan,
+        Color::LightRed,
+    ];
+    let hash = tag.to_lowercase().bytes().fold(0usize, |hash, byte| {
+        hash.wrapping_mul(31).wrapping_add(byte as usize)
+    });
+    COLORS[hash % COLORS.len()]
+}
 
-    let done_list = build_task_list(&app.done.items, "Done", app.current_column == Column::Done);
-    let mut done_list_state = ListState::default().with_selected(app.done.index);
+// splits `text` into spans so the chars at `matched` stand out
+fn highlight<'a>(text: &str, matched: &[usize]) -> Vec<Span<'a>> {
+    let style = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::UNDERLINED);
+    let mut spans: Vec<Span> = vec![];
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (position, c) in text.chars().enumerate() {
+        let is_matched = matched.contains(&position);
+        if is_matched != run_matched && !run.is_empty() {
+            let text = std::mem::take(&mut run);
+            spans.push(if run_matched {
+                Span::styled(text, style)
+            } else {
+                Span::raw(text)
+            });
+        }
+        run_matched = is_matched;
+        run.push(c);
+    }
+    spans.push(if run_matched {
+        Span::styled(run, style)
+    } else {
+        Span::raw(run)
+    });
+    spans
+}
+
+fn render_overview(app: &mut App, chunks: &[Rect], f: &mut Frame<impl Backend>) {
+    for (i, (column, chunk)) in app.columns.iter().zip(chunks).enumerate() {
+        let title = match column.limit() {
+            Some(limit) => format!("{} {}/{}", column.name, column.tasks.items.len(), limit),
+            None => column.name.clone(),
+        };
+        // with a filter the list only has the tasks it shows, the cursor has to be counted among those
+        let visible: Vec<&Task> = column
+            .tasks
+            .items
+            .iter()
+            .filter(|task| app.is_visible(task))
+            .collect();
+        let selected = column.tasks.index.and_then(|index| {
+            let task = column.tasks.items.get(index)?;
+            visible.iter().position(|shown| std::ptr::eq(*shown, task))
+        });
+        let list = build_task_list(app, &visible, &title, app.current_column == i);
+        let mut list_state = ListState::default().with_selected(selected);
+        f.render_stateful_widget(list, *chunk, &mut list_state);
+    }
+}
 
-    f.render_stateful_widget(todo_list, chunks[0], &mut todo_list_state);
-    f.render_stateful_widget(wip_list, chunks[1], &mut wip_list_state);
-    f.render_stateful_widget(done_list, chunks[2], &mut done_list_state);
+// the middle of the screen, where popups go
+fn centered(area: Rect) -> Rect {
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .margin(1)
+        .constraints(
+            [
+                Constraint::Percentage(30),
+                Constraint::Percentage(40),
+                Constraint::Percentage(30),
+            ]
+            .as_ref(),
+        )
+        .split(area)[1]
 }